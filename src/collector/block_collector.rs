@@ -3,15 +3,27 @@ use std::sync::Arc;
 use alloy::{providers::Provider, rpc::types::Header};
 use async_trait::async_trait;
 
+use crate::collector::FailoverProvider;
 use crate::types::{Collector, CollectorStream};
 
 pub struct BlockCollector {
     provider: Arc<dyn Provider>,
+    failover: Option<Arc<FailoverProvider>>,
 }
 
 impl BlockCollector {
     pub fn new(provider: Arc<dyn Provider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            failover: None,
+        }
+    }
+
+    pub fn new_with_failover(failover: Arc<FailoverProvider>) -> Self {
+        Self {
+            provider: failover.active(),
+            failover: Some(failover),
+        }
     }
 }
 
@@ -22,6 +34,13 @@ impl Collector<Header> for BlockCollector {
     }
 
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Header>> {
+        if let Some(failover) = &self.failover {
+            return Ok(failover.subscribe(|provider| async move {
+                let stream = provider.subscribe_blocks().await?;
+                Ok(Box::pin(stream.into_stream()) as CollectorStream<'_, Header>)
+            }));
+        }
+
         let stream = self.provider.subscribe_blocks().await?;
 
         Ok(Box::pin(stream.into_stream()))
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::{
+    providers::Provider,
+    rpc::types::{
+        eth::BlockNumberOrTag,
+        trace::{
+            common::TraceResult,
+            geth::{CallConfig, CallFrame, GethDebugTracingOptions, PreStateConfig, PreStateFrame},
+            parity::LocalizedTransactionTrace,
+        },
+        Header,
+    },
+    transports::{RpcError, TransportErrorKind, TransportResult},
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tracing::{error, warn};
+
+use crate::types::{Collector, CollectorStream};
+use crate::METHOD_NOT_FOUND;
+
+/// Tracer used by [`CallTraceCollector`] to trace every transaction of a block.
+#[derive(Debug, Clone)]
+pub enum BlockTracer {
+    /// `debug_traceBlockByNumber` with the built-in `callTracer`
+    Call(CallConfig),
+    /// `debug_traceBlockByNumber` with the built-in `prestateTracer`
+    PreState(PreStateConfig),
+    /// `trace_block` of the parity-style `trace` namespace, available on Erigon and Reth
+    Parity,
+}
+
+impl Default for BlockTracer {
+    fn default() -> Self {
+        Self::Call(CallConfig::default())
+    }
+}
+
+/// Traces of all transactions in a block, in the shape produced by the configured [`BlockTracer`].
+#[derive(Debug, Clone)]
+pub enum BlockTraces {
+    Call(Vec<TraceResult<CallFrame, String>>),
+    PreState(Vec<TraceResult<PreStateFrame, String>>),
+    Parity(Vec<LocalizedTransactionTrace>),
+}
+
+pub struct CallTraceCollector {
+    provider: Arc<dyn Provider>,
+    tracer: BlockTracer,
+    retry_interval: Duration,
+    max_retries: usize,
+}
+
+impl CallTraceCollector {
+    pub fn new(provider: Arc<dyn Provider>, tracer: BlockTracer) -> Self {
+        Self::new_with_config(provider, tracer, Duration::from_millis(50))
+    }
+
+    /// Create a new `CallTraceCollector` with a custom retry interval. A retry will happen when the client hasn't
+    /// got the block yet, e.g. returns "header not found"
+    pub fn new_with_config(
+        provider: Arc<dyn Provider>,
+        tracer: BlockTracer,
+        retry_interval: Duration,
+    ) -> Self {
+        Self {
+            provider,
+            tracer,
+            retry_interval,
+            max_retries: 100,
+        }
+    }
+
+    /// Give up on a block the node still doesn't know after `max_retries` retries, 100 by default
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    async fn trace_block(&self, block_number: u64) -> TransportResult<BlockTraces> {
+        let provider = self.provider.root();
+        let block = BlockNumberOrTag::Number(block_number);
+
+        let traces = match &self.tracer {
+            BlockTracer::Call(config) => {
+                let options = GethDebugTracingOptions::call_tracer(*config);
+                BlockTraces::Call(
+                    provider
+                        .raw_request("debug_traceBlockByNumber".into(), (block, options))
+                        .await?,
+                )
+            }
+            BlockTracer::PreState(config) => {
+                let options = GethDebugTracingOptions::prestate_tracer(*config);
+                BlockTraces::PreState(
+                    provider
+                        .raw_request("debug_traceBlockByNumber".into(), (block, options))
+                        .await?,
+                )
+            }
+            BlockTracer::Parity => {
+                BlockTraces::Parity(provider.raw_request("trace_block".into(), (block,)).await?)
+            }
+        };
+
+        Ok(traces)
+    }
+}
+
+#[async_trait]
+impl Collector<(Header, BlockTraces)> for CallTraceCollector {
+    fn name(&self) -> &str {
+        "CallTraceCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, (Header, BlockTraces)>> {
+        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
+
+        let stream = async_stream::stream! {
+            while let Some(block) = stream.next().await {
+                let block_number = block.number;
+                let mut attempts = 0;
+
+                loop {
+                    match self.trace_block(block_number).await {
+                        Ok(traces) => {
+                            yield (block, traces);
+                        }
+                        Err(e) if is_block_not_found(&e) && attempts < self.max_retries => {
+                            if attempts % 5 == 0 {
+                                warn!(block = block_number, "block not found yet");
+                            }
+
+                            attempts += 1;
+                            tokio::time::sleep(self.retry_interval).await;
+                            continue;
+                        }
+                        Err(e) => {
+                            error!(block = block_number, "fail to trace block: {e:#}");
+                        }
+                    };
+
+                    break;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Nodes report a block that isn't imported yet as an RPC error instead of an empty result, e.g. "header not found"
+/// on Geth, "block #1 not found" on Reth, or "unknown block" on Nethermind.
+fn is_block_not_found(err: &RpcError<TransportErrorKind>) -> bool {
+    let payload = match err.as_error_resp() {
+        Some(v) if v.code != METHOD_NOT_FOUND => v,
+        _ => return false,
+    };
+
+    let message = payload.message.to_lowercase();
+
+    message.contains("header not found")
+        || message.contains("unknown block")
+        || (message.starts_with("block") && message.ends_with("not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::json_rpc::ErrorPayload;
+
+    use super::*;
+
+    fn error(code: i64, message: &str) -> RpcError<TransportErrorKind> {
+        RpcError::ErrorResp(ErrorPayload {
+            code,
+            message: message.to_string().into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn detects_blocks_not_imported_yet() {
+        assert!(is_block_not_found(&error(-32000, "header not found")));
+        assert!(is_block_not_found(&error(-32001, "block #123 not found")));
+        assert!(is_block_not_found(&error(-32000, "Block 0xabc not found")));
+        assert!(is_block_not_found(&error(-32000, "unknown block")));
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        assert!(!is_block_not_found(&error(-32601, "Method not found")));
+        assert!(!is_block_not_found(&error(
+            -32601,
+            "the method debug_traceBlockByNumber does not exist/is not available"
+        )));
+        assert!(!is_block_not_found(&error(-32000, "tracer not found")));
+        assert!(!is_block_not_found(&error(-32000, "execution timeout")));
+        assert!(!is_block_not_found(&RpcError::NullResp));
+    }
+}
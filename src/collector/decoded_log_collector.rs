@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, BlockHash, TxHash, B256},
+    providers::Provider,
+    rpc::types::eth::{Filter, Log},
+    sol_types::{SolEvent, SolEventInterface},
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
+
+use crate::collector::LogCollector;
+use crate::types::{Collector, CollectorStream};
+
+/// Where a decoded event was emitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMeta {
+    pub address: Address,
+    pub block_hash: Option<BlockHash>,
+    pub block_number: Option<u64>,
+    pub block_timestamp: Option<u64>,
+    pub transaction_hash: Option<TxHash>,
+    pub transaction_index: Option<u64>,
+    pub log_index: Option<u64>,
+    pub removed: bool,
+}
+
+impl From<&Log> for LogMeta {
+    fn from(log: &Log) -> Self {
+        Self {
+            address: log.address(),
+            block_hash: log.block_hash,
+            block_number: log.block_number,
+            block_timestamp: log.block_timestamp,
+            transaction_hash: log.transaction_hash,
+            transaction_index: log.transaction_index,
+            log_index: log.log_index,
+            removed: log.removed,
+        }
+    }
+}
+
+/// A log that matched the topic filter but couldn't be decoded into the event type.
+#[derive(Debug, Clone)]
+pub struct LogDecodeError {
+    pub log: Log,
+    pub error: String,
+}
+
+/// Subscribes to the logs of one `sol!` event, or of a `sol!` events enum, and yields them decoded along with where
+/// they were emitted.
+pub struct DecodedLogCollector<E> {
+    logs: LogCollector,
+    decode: fn(&Log) -> alloy::sol_types::Result<E>,
+    errors: Option<UnboundedSender<LogDecodeError>>,
+}
+
+impl<E: SolEvent> DecodedLogCollector<E> {
+    /// Create a new `DecodedLogCollector` for the logs of event `E` matching `filter`. The event signature topic is
+    /// set on the filter.
+    pub fn new(provider: Arc<dyn Provider>, filter: Filter) -> Self {
+        Self::from_log_collector(LogCollector::new(
+            provider,
+            filter.event_signature(E::SIGNATURE_HASH),
+        ))
+    }
+
+    /// Create a new `DecodedLogCollector` for event `E` on top of a configured `LogCollector`, e.g. one waiting for
+    /// confirmations. Its filter should select the event signature topic.
+    pub fn from_log_collector(logs: LogCollector) -> Self {
+        Self {
+            logs,
+            decode: |log| E::decode_log_data(log.data()),
+            errors: None,
+        }
+    }
+}
+
+impl<E: SolEventInterface> DecodedLogCollector<E> {
+    /// Create a new `DecodedLogCollector` for the logs of any event in the `sol!` events enum `E` matching `filter`.
+    /// `selectors` is the enum's `SELECTORS`, which are set as the event signature topics on the filter.
+    pub fn new_with_events(
+        provider: Arc<dyn Provider>,
+        filter: Filter,
+        selectors: &[[u8; 32]],
+    ) -> Self {
+        let topics: Vec<B256> = selectors.iter().copied().map(B256::from).collect();
+        Self::from_log_collector_with_events(LogCollector::new(
+            provider,
+            filter.event_signature(topics),
+        ))
+    }
+
+    /// Create a new `DecodedLogCollector` for the events enum `E` on top of a configured `LogCollector`. Its filter
+    /// should select the event signature topics.
+    pub fn from_log_collector_with_events(logs: LogCollector) -> Self {
+        Self {
+            logs,
+            decode: |log| E::decode_raw_log(log.topics(), &log.data().data),
+            errors: None,
+        }
+    }
+}
+
+impl<E> DecodedLogCollector<E> {
+    /// Send logs that fail to decode to `sender` instead of only logging them
+    pub fn with_error_sender(mut self, sender: UnboundedSender<LogDecodeError>) -> Self {
+        self.errors = Some(sender);
+        self
+    }
+
+    fn report(&self, log: Log, error: alloy::sol_types::Error) {
+        match &self.errors {
+            Some(sender) => {
+                let report = LogDecodeError {
+                    log,
+                    error: format!("{error:#}"),
+                };
+
+                if sender.send(report).is_err() {
+                    warn!("undecodable log dropped, error channel is closed");
+                }
+            }
+            None => {
+                warn!(address = ?log.address(), tx = ?log.transaction_hash, "fail to decode log: {error:#}")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<E> Collector<(E, LogMeta)> for DecodedLogCollector<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "DecodedLogCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, (E, LogMeta)>> {
+        let mut logs = self.logs.get_event_stream().await?;
+
+        let stream = async_stream::stream! {
+            while let Some(log) = logs.next().await {
+                match (self.decode)(&log) {
+                    Ok(event) => yield (event, LogMeta::from(&log)),
+                    Err(e) => self.report(log, e),
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
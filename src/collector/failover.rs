@@ -0,0 +1,286 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use alloy::providers::Provider;
+use futures::{
+    future::{self, Either},
+    StreamExt,
+};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+use crate::types::CollectorStream;
+
+/// An ordered list of endpoints for the same chain. Subscriptions made through it follow the first endpoint that is
+/// healthy, i.e. its head advanced within the stall timeout and isn't too far behind the other endpoints. Once an
+/// endpoint earlier in the list recovers, subscriptions fail back to it.
+pub struct FailoverProvider {
+    providers: Vec<Arc<dyn Provider>>,
+    stall_timeout: Duration,
+    max_lag: u64,
+    health_check_interval: Duration,
+    retry_interval: Duration,
+
+    active_sender: Arc<watch::Sender<usize>>,
+    active: watch::Receiver<usize>,
+    monitor: OnceLock<()>,
+}
+
+impl FailoverProvider {
+    pub fn new(providers: Vec<Arc<dyn Provider>>) -> Self {
+        assert!(!providers.is_empty(), "at least one provider is required");
+
+        let (active_sender, active) = watch::channel(0);
+
+        Self {
+            providers,
+            stall_timeout: Duration::from_secs(30),
+            max_lag: 3,
+            health_check_interval: Duration::from_secs(1),
+            retry_interval: Duration::from_secs(1),
+            active_sender: Arc::new(active_sender),
+            active,
+            monitor: OnceLock::new(),
+        }
+    }
+
+    /// An endpoint whose head hasn't advanced for this long is considered stalled
+    pub fn with_stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = stall_timeout;
+        self
+    }
+
+    /// An endpoint whose head is more than this many blocks behind the highest one is considered stalled
+    pub fn with_max_lag(mut self, max_lag: u64) -> Self {
+        self.max_lag = max_lag;
+        self
+    }
+
+    pub fn with_health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = interval;
+        self
+    }
+
+    /// How long to wait before subscribing again after a subscription failed or ended
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// The endpoint subscriptions currently follow
+    pub fn active(&self) -> Arc<dyn Provider> {
+        self.providers[*self.active.borrow()].clone()
+    }
+
+    /// Subscribe through the active endpoint, and subscribe again on another one whenever the active endpoint changes.
+    pub(crate) fn subscribe<'a, T, F, Fut>(&'a self, subscribe: F) -> CollectorStream<'a, T>
+    where
+        T: Send + 'a,
+        F: Fn(&'a dyn Provider) -> Fut + Send + Sync + 'a,
+        Fut: Future<Output = eyre::Result<CollectorStream<'a, T>>> + Send + 'a,
+    {
+        self.spawn_monitor();
+
+        let mut active = self.active.clone();
+
+        let stream = async_stream::stream! {
+            loop {
+                let index = *active.borrow_and_update();
+
+                let mut stream = match subscribe(self.providers[index].as_ref()).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        error!(endpoint = index, "fail to subscribe: {err:#}");
+
+                        let retry = Box::pin(tokio::time::sleep(self.retry_interval));
+                        let changed = Box::pin(active.changed());
+
+                        if let Either::Right((Err(_), _)) = future::select(retry, changed).await {
+                            break;
+                        }
+
+                        continue;
+                    }
+                };
+
+                loop {
+                    let next = match future::select(stream.next(), Box::pin(active.changed())).await {
+                        Either::Left((item, _)) => Ok(item),
+                        Either::Right((changed, _)) => Err(changed),
+                    };
+
+                    match next {
+                        Ok(Some(item)) => yield item,
+                        Ok(None) => {
+                            warn!(endpoint = index, "subscription ended, subscribing again");
+                            tokio::time::sleep(self.retry_interval).await;
+                            break;
+                        }
+                        Err(Ok(_)) => {
+                            info!(from = index, to = *active.borrow(), "switching endpoint");
+                            break;
+                        }
+                        Err(Err(_)) => return,
+                    }
+                }
+            }
+        };
+
+        Box::pin(stream)
+    }
+
+    /// Start checking the endpoints in the background, once. The check stops when the provider and all subscriptions
+    /// made through it are dropped.
+    fn spawn_monitor(&self) {
+        if self.providers.len() < 2 {
+            return;
+        }
+
+        self.monitor.get_or_init(|| {
+            let providers = self.providers.clone();
+            let sender = self.active_sender.clone();
+            let stall_timeout = self.stall_timeout;
+            let max_lag = self.max_lag;
+            let interval = self.health_check_interval;
+
+            tokio::spawn(async move {
+                let now = Instant::now();
+                let mut heads = vec![(0u64, now); providers.len()];
+
+                while !sender.is_closed() {
+                    let numbers = future::join_all(providers.iter().map(|provider| {
+                        tokio::time::timeout(interval, provider.get_block_number())
+                    }))
+                    .await;
+
+                    let now = Instant::now();
+
+                    for (index, number) in numbers.into_iter().enumerate() {
+                        match number {
+                            Ok(Ok(number)) if number > heads[index].0 => {
+                                heads[index] = (number, now)
+                            }
+                            Ok(Ok(_)) => {}
+                            Ok(Err(err)) => debug!(endpoint = index, "fail to get head: {err:#}"),
+                            Err(_) => debug!(endpoint = index, "timed out getting head"),
+                        }
+                    }
+
+                    if let Some(healthy) = pick_active(&heads, now, stall_timeout, max_lag) {
+                        sender.send_if_modified(|active| {
+                            if *active == healthy {
+                                return false;
+                            }
+
+                            warn!(
+                                from = *active,
+                                to = healthy,
+                                head = heads[healthy].0,
+                                "failing over"
+                            );
+                            *active = healthy;
+                            true
+                        });
+                    }
+
+                    tokio::time::sleep(interval).await;
+                }
+            });
+        });
+    }
+}
+
+/// The first endpoint whose head, given as its number and when it last advanced, advanced within `stall_timeout` and
+/// is at most `max_lag` blocks behind the highest head. `None` if every endpoint is stalled.
+fn pick_active(
+    heads: &[(u64, Instant)],
+    now: Instant,
+    stall_timeout: Duration,
+    max_lag: u64,
+) -> Option<usize> {
+    let best = heads
+        .iter()
+        .map(|(number, _)| *number)
+        .max()
+        .unwrap_or_default();
+
+    heads.iter().position(|(number, advanced_at)| {
+        now.duration_since(*advanced_at) < stall_timeout && best - number <= max_lag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+    const MAX_LAG: u64 = 3;
+
+    /// Far enough from the start of the clock to go back by the stall timeout
+    fn later() -> Instant {
+        Instant::now() + STALL_TIMEOUT
+    }
+
+    fn pick(heads: &[(u64, Instant)], now: Instant) -> Option<usize> {
+        pick_active(heads, now, STALL_TIMEOUT, MAX_LAG)
+    }
+
+    #[test]
+    fn prefers_the_first_healthy_endpoint() {
+        let now = later();
+
+        assert_eq!(pick(&[(100, now), (100, now)], now), Some(0));
+        assert_eq!(pick(&[(100, now), (101, now)], now), Some(0));
+        assert_eq!(pick(&[(0, now), (0, now)], now), Some(0));
+    }
+
+    #[test]
+    fn skips_stalled_endpoints() {
+        let now = later();
+        let stalled = now - STALL_TIMEOUT;
+
+        assert_eq!(pick(&[(100, stalled), (100, now)], now), Some(1));
+        assert_eq!(
+            pick(
+                &[
+                    (100, now - STALL_TIMEOUT + Duration::from_secs(1)),
+                    (100, now)
+                ],
+                now
+            ),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn skips_lagging_endpoints() {
+        let now = later();
+
+        assert_eq!(pick(&[(100, now), (103, now)], now), Some(0));
+        assert_eq!(pick(&[(100, now), (104, now)], now), Some(1));
+        assert_eq!(pick(&[(100, now), (90, now), (104, now)], now), Some(2));
+    }
+
+    #[test]
+    fn keeps_none_when_every_endpoint_is_down() {
+        let now = later();
+        let stalled = now - STALL_TIMEOUT;
+
+        assert_eq!(pick(&[(100, stalled), (104, stalled)], now), None);
+        assert_eq!(pick(&[], now), None);
+    }
+
+    #[test]
+    fn fails_back_once_the_primary_recovers() {
+        let start = Instant::now();
+        let later = start + STALL_TIMEOUT;
+
+        // the primary stalled at 100 while the backup kept going
+        assert_eq!(pick(&[(100, start), (110, later)], later), Some(1));
+
+        // the primary caught up
+        let now = later + Duration::from_secs(1);
+        assert_eq!(pick(&[(109, now), (110, later)], now), Some(0));
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::{
+    providers::Provider,
+    rpc::types::{
+        eth::{BlockNumberOrTag, Filter, Log},
+        Header,
+    },
+};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use tracing::error;
+
+use crate::types::{Collector, CollectorStream};
+
+/// When a block counts as confirmed for [`FinalityCollector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationLevel {
+    /// The chain is at least this many blocks ahead of it
+    Depth(u64),
+    /// At or below the `safe` block
+    Safe,
+    /// At or below the `finalized` block
+    Finalized,
+}
+
+#[derive(Debug, Clone)]
+pub enum FinalityEvent {
+    /// A new head, as delivered by `BlockCollector`
+    Latest(Header),
+    /// The `safe` block advanced to this number
+    Safe(u64),
+    /// The `finalized` block advanced to this number
+    Finalized(u64),
+    /// A canonical block that reached the configured confirmation level, with its logs matching the configured filter
+    Confirmed(Header, Vec<Log>),
+}
+
+enum Input {
+    Head(Box<Header>),
+    Poll,
+}
+
+/// Follows the head like `BlockCollector`, and polls the `safe` and `finalized` block tags alongside it.
+pub struct FinalityCollector {
+    provider: Arc<dyn Provider>,
+    poll_interval: Duration,
+    confirmation: Option<ConfirmationLevel>,
+    filter: Option<Filter>,
+}
+
+impl FinalityCollector {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self::new_with_config(provider, Duration::from_secs(12))
+    }
+
+    /// Create a new `FinalityCollector` polling the `safe` and `finalized` block tags every `poll_interval`
+    pub fn new_with_config(provider: Arc<dyn Provider>, poll_interval: Duration) -> Self {
+        Self {
+            provider,
+            poll_interval,
+            confirmation: None,
+            filter: None,
+        }
+    }
+
+    /// Also emit [`FinalityEvent::Confirmed`] for every block once it reaches `confirmation`
+    pub fn with_confirmation(mut self, confirmation: ConfirmationLevel) -> Self {
+        self.confirmation = Some(confirmation);
+        self
+    }
+
+    /// Fetch the logs matching `filter` for every confirmed block
+    pub fn with_log_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    async fn get_tag_number(&self, tag: BlockNumberOrTag) -> Option<u64> {
+        match self.provider.get_block_by_number(tag).await {
+            Ok(Some(block)) => Some(block.header.number),
+            Ok(None) => {
+                error!(?tag, "block not found");
+                None
+            }
+            Err(e) => {
+                error!(?tag, "fail to get block: {e:#}");
+                None
+            }
+        }
+    }
+
+    /// Fetch the canonical block at `number` along with its logs
+    async fn get_confirmed(&self, number: u64) -> eyre::Result<(Header, Vec<Log>)> {
+        let block = self
+            .provider
+            .get_block_by_number(number.into())
+            .await?
+            .ok_or_else(|| eyre::eyre!("block {number} not found"))?;
+
+        let logs = match &self.filter {
+            Some(filter) => {
+                let filter = filter.clone().at_block_hash(block.header.hash);
+                self.provider.get_logs(&filter).await?
+            }
+            None => vec![],
+        };
+
+        Ok((block.header, logs))
+    }
+}
+
+#[async_trait]
+impl Collector<FinalityEvent> for FinalityCollector {
+    fn name(&self) -> &str {
+        "FinalityCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, FinalityEvent>> {
+        let heads = self.provider.subscribe_blocks().await?.into_stream();
+
+        let polls = async_stream::stream! {
+            loop {
+                yield Input::Poll;
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        };
+
+        let mut inputs = stream::select(heads.map(|v| Input::Head(Box::new(v))), Box::pin(polls));
+
+        let stream = async_stream::stream! {
+            let mut head = 0;
+            let mut safe = 0;
+            let mut finalized = 0;
+            let mut next_confirmed = None;
+
+            while let Some(input) = inputs.next().await {
+                match input {
+                    Input::Head(header) => {
+                        head = header.number;
+                        yield FinalityEvent::Latest(*header);
+                    }
+                    Input::Poll => {
+                        if let Some(number) = self.get_tag_number(BlockNumberOrTag::Safe).await {
+                            if number > safe {
+                                safe = number;
+                                yield FinalityEvent::Safe(number);
+                            }
+                        }
+
+                        if let Some(number) = self.get_tag_number(BlockNumberOrTag::Finalized).await {
+                            if number > finalized {
+                                finalized = number;
+                                yield FinalityEvent::Finalized(number);
+                            }
+                        }
+                    }
+                }
+
+                let confirmed = match self.confirmation {
+                    Some(ConfirmationLevel::Depth(depth)) if head > depth => head - depth,
+                    Some(ConfirmationLevel::Safe) => safe,
+                    Some(ConfirmationLevel::Finalized) => finalized,
+                    _ => continue,
+                };
+
+                if confirmed == 0 {
+                    continue;
+                }
+
+                // start from the first confirmed block seen instead of replaying the chain's history
+                let mut next = next_confirmed.unwrap_or(confirmed);
+
+                while next <= confirmed {
+                    match self.get_confirmed(next).await {
+                        Ok((header, logs)) => yield FinalityEvent::Confirmed(header, logs),
+                        Err(e) => {
+                            error!(block = next, "fail to get confirmed block: {e:#}");
+                            break;
+                        }
+                    }
+
+                    next += 1;
+                }
+
+                next_confirmed = Some(next);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use alloy::{primitives::B256, providers::Provider, rpc::types::Header};
+use async_trait::async_trait;
+use futures::{stream::select_all, StreamExt};
+
+use crate::types::{Collector, CollectorStream};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadLagEvent {
+    /// `provider` received a new head, `latency` after the first provider received the same block
+    NewHead {
+        provider: String,
+        number: u64,
+        hash: B256,
+        latency: Duration,
+    },
+    /// `provider` fell more than the allowed number of blocks behind the highest head seen by any provider. `head`
+    /// is `None` if it never received a head, e.g. because it can't be subscribed to.
+    Lagging {
+        provider: String,
+        head: Option<u64>,
+        best: u64,
+    },
+    /// `provider` caught up again after lagging
+    Recovered { provider: String, head: u64 },
+}
+
+impl fmt::Display for HeadLagEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NewHead {
+                provider,
+                number,
+                latency,
+                ..
+            } => write!(f, "{provider} received block {number} after {latency:?}"),
+            Self::Lagging {
+                provider,
+                head: Some(head),
+                best,
+            } => write!(
+                f,
+                "{provider} is {} blocks behind (head {head}, best {best})",
+                best - head
+            ),
+            Self::Lagging {
+                provider,
+                head: None,
+                best,
+            } => write!(f, "{provider} received no head (best {best})"),
+            Self::Recovered { provider, head } => {
+                write!(f, "{provider} caught up at block {head}")
+            }
+        }
+    }
+}
+
+/// Subscribes to new heads on several providers of the same chain and reports how far behind each of them is.
+pub struct HeadLagCollector {
+    providers: Vec<(String, Arc<dyn Provider>)>,
+    max_lag: u64,
+    retry_interval: Duration,
+}
+
+impl HeadLagCollector {
+    pub fn new(providers: Vec<(String, Arc<dyn Provider>)>) -> Self {
+        Self::new_with_config(providers, 2)
+    }
+
+    /// Create a new `HeadLagCollector` that reports a provider as lagging once it's more than `max_lag` blocks behind
+    pub fn new_with_config(providers: Vec<(String, Arc<dyn Provider>)>, max_lag: u64) -> Self {
+        Self {
+            providers,
+            max_lag,
+            retry_interval: Duration::from_secs(5),
+        }
+    }
+
+    /// How long to wait before subscribing again to a provider that failed or closed its subscription, 5s by default
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// New heads of the provider at `index`, subscribing again whenever the subscription fails or ends
+    fn heads(&self, index: usize) -> CollectorStream<'_, (usize, Header)> {
+        let (name, provider) = &self.providers[index];
+
+        Box::pin(async_stream::stream! {
+            loop {
+                match provider.subscribe_blocks().await {
+                    Ok(subscription) => {
+                        let mut stream = subscription.into_stream();
+                        while let Some(header) = stream.next().await {
+                            yield (index, header);
+                        }
+                        tracing::warn!(provider = name, "head subscription ended");
+                    }
+                    Err(err) => {
+                        tracing::warn!(provider = name, "fail to subscribe to heads: {err:#}");
+                    }
+                }
+
+                tokio::time::sleep(self.retry_interval).await;
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Collector<HeadLagEvent> for HeadLagCollector {
+    fn name(&self) -> &str {
+        "HeadLagCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, HeadLagEvent>> {
+        let mut heads = select_all((0..self.providers.len()).map(|index| self.heads(index)));
+
+        let stream = async_stream::stream! {
+            let mut first_seen = BTreeMap::<u64, Vec<(B256, Instant)>>::new();
+            let mut latest = vec![None::<u64>; self.providers.len()];
+            let mut lagging = vec![false; self.providers.len()];
+            // first head received by any provider, providers without heads are compared as if stuck right before it
+            let mut start = None;
+
+            while let Some((index, header)) = heads.next().await {
+                let now = Instant::now();
+                let seen = first_seen.entry(header.number).or_default();
+
+                let first = match seen.iter().find(|(hash, _)| *hash == header.hash) {
+                    Some((_, first)) => *first,
+                    None => {
+                        seen.push((header.hash, now));
+                        now
+                    }
+                };
+
+                latest[index] = latest[index].max(Some(header.number));
+                let best = latest.iter().copied().max().flatten().unwrap_or_default();
+                let start = *start.get_or_insert(header.number);
+
+                // only keep recent blocks around to measure the latency of providers that are behind
+                first_seen = first_seen.split_off(&best.saturating_sub(self.max_lag + 64));
+
+                yield HeadLagEvent::NewHead {
+                    provider: self.providers[index].0.clone(),
+                    number: header.number,
+                    hash: header.hash,
+                    latency: now - first,
+                };
+
+                for (index, head) in latest.iter().enumerate() {
+                    let compared = head.unwrap_or(start.saturating_sub(1));
+                    let is_lagging = best.saturating_sub(compared) > self.max_lag;
+                    if is_lagging == lagging[index] {
+                        continue;
+                    }
+
+                    lagging[index] = is_lagging;
+
+                    let provider = self.providers[index].0.clone();
+                    yield match is_lagging {
+                        true => HeadLagEvent::Lagging { provider, head: *head, best },
+                        false => HeadLagEvent::Recovered { provider, head: compared },
+                    };
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -3,13 +3,36 @@ use std::time::{Duration, Instant};
 use crate::types::{Collector, CollectorStream};
 use async_trait::async_trait;
 
+/// What to do with ticks that became due while the stream wasn't polled, e.g. because the process was suspended or
+/// the runtime was overloaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissedTickPolicy {
+    /// Fire every missed tick right away
+    Burst,
+    /// Drop missed ticks and wait for the next one on the original schedule
+    #[default]
+    Skip,
+    /// Fire a single tick right away for all missed ones. `IntervalCollector` then restarts its interval from that
+    /// tick, whereas `ScheduleCollector` reports it as the latest missed tick and stays on its schedule afterwards.
+    Delay,
+}
+
 pub struct IntervalCollector {
     interval: Duration,
+    missed_tick_policy: MissedTickPolicy,
 }
 
 impl IntervalCollector {
     pub fn new(interval: Duration) -> Self {
-        Self { interval }
+        Self {
+            interval,
+            missed_tick_policy: MissedTickPolicy::default(),
+        }
+    }
+
+    pub fn with_missed_tick_policy(mut self, policy: MissedTickPolicy) -> Self {
+        self.missed_tick_policy = policy;
+        self
     }
 }
 
@@ -21,12 +44,55 @@ impl Collector<Instant> for IntervalCollector {
 
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Instant>> {
         let stream = async_stream::stream! {
+            let mut next_tick = Instant::now() + self.interval;
+
             loop {
-                tokio::time::sleep(self.interval).await;
+                tokio::time::sleep_until(next_tick.into()).await;
                 yield Instant::now();
+
+                next_tick += self.interval;
+
+                let now = Instant::now();
+                if next_tick > now {
+                    continue;
+                }
+
+                match self.missed_tick_policy {
+                    // with a zero interval every tick is always due, there's nothing to skip to
+                    MissedTickPolicy::Burst => {}
+                    MissedTickPolicy::Skip if self.interval.is_zero() => {}
+                    MissedTickPolicy::Skip => {
+                        while next_tick <= now {
+                            next_tick += self.interval;
+                        }
+                    }
+                    MissedTickPolicy::Delay => next_tick = now,
+                }
             }
         };
 
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn zero_interval_keeps_ticking() {
+        for policy in [
+            MissedTickPolicy::Burst,
+            MissedTickPolicy::Skip,
+            MissedTickPolicy::Delay,
+        ] {
+            let collector = IntervalCollector::new(Duration::ZERO).with_missed_tick_policy(policy);
+            let stream = collector.get_event_stream().await.unwrap();
+
+            let ticks = tokio::time::timeout(Duration::from_secs(5), stream.take(3).count()).await;
+            assert_eq!(ticks, Ok(3), "{policy:?}");
+        }
+    }
+}
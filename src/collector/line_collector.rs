@@ -0,0 +1,566 @@
+use std::io::{ErrorKind, SeekFrom};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::WrapErr;
+use serde::de::DeserializeOwned;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
+    sync::mpsc::UnboundedSender,
+};
+use tracing::{error, warn};
+
+use crate::types::{Collector, CollectorStream};
+
+/// A line that couldn't be deserialised into an event.
+#[derive(Debug, Clone)]
+pub struct LineError {
+    /// The collector that read the line
+    pub source: &'static str,
+    pub line: String,
+    pub error: String,
+}
+
+/// Where malformed lines end up. They are logged if no channel is set.
+#[derive(Debug, Clone, Default)]
+struct ErrorReporter(Option<UnboundedSender<LineError>>);
+
+impl ErrorReporter {
+    fn parse<E: DeserializeOwned>(&self, source: &'static str, line: &str) -> Option<E> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        let err = match serde_json::from_str(line) {
+            Ok(event) => return Some(event),
+            Err(err) => err,
+        };
+
+        let report = LineError {
+            source,
+            line: line.to_string(),
+            error: format!("{err:#}"),
+        };
+
+        match &self.0 {
+            Some(sender) => {
+                if sender.send(report).is_err() {
+                    warn!(source, "malformed line dropped, error channel is closed");
+                }
+            }
+            None => warn!(source, line, "fail to parse line: {err:#}"),
+        }
+
+        None
+    }
+}
+
+/// Reads newline-delimited JSON events from the process' stdin.
+pub struct StdinCollector<E> {
+    errors: ErrorReporter,
+    _phantom: PhantomData<fn() -> E>,
+}
+
+impl<E> StdinCollector<E> {
+    pub fn new() -> Self {
+        Self {
+            errors: ErrorReporter::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_error_sender(mut self, sender: UnboundedSender<LineError>) -> Self {
+        self.errors = ErrorReporter(Some(sender));
+        self
+    }
+}
+
+impl<E> Default for StdinCollector<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<E> Collector<E> for StdinCollector<E>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "StdinCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+        let stream = async_stream::stream! {
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) => {
+                        if let Some(event) = self.errors.parse("StdinCollector", &line) {
+                            yield event;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        error!("fail to read stdin: {err:#}");
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Follows a file like `tail -F` and reads newline-delimited JSON events appended to it. The file is reopened from the
+/// start when it gets truncated or replaced, e.g. by log rotation. A file that doesn't exist yet is waited for, and read
+/// from the start once created.
+pub struct FileTailCollector<E> {
+    path: PathBuf,
+    from_start: bool,
+    poll_interval: Duration,
+    errors: ErrorReporter,
+    _phantom: PhantomData<fn() -> E>,
+}
+
+impl<E> FileTailCollector<E> {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            from_start: false,
+            poll_interval: Duration::from_millis(250),
+            errors: ErrorReporter::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Also emit the lines already in the file when the collector starts
+    pub fn from_start(mut self) -> Self {
+        self.from_start = true;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_error_sender(mut self, sender: UnboundedSender<LineError>) -> Self {
+        self.errors = ErrorReporter(Some(sender));
+        self
+    }
+
+    /// Poll until the file at `path` can be opened
+    async fn wait_for_file(&self) -> File {
+        loop {
+            tokio::time::sleep(self.poll_interval).await;
+
+            match File::open(&self.path).await {
+                Ok(file) => return file,
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => error!(path = ?self.path, "fail to open file: {err:#}"),
+            }
+        }
+    }
+
+    /// Whether the file at `path` is no longer the one being read from, or was truncated below `position`
+    async fn is_rotated(&self, file: &File, position: u64) -> bool {
+        let current = match tokio::fs::metadata(&self.path).await {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+
+        if current.len() < position {
+            return true;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            if let Ok(opened) = file.metadata().await {
+                return opened.ino() != current.ino() || opened.dev() != current.dev();
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = file;
+
+        false
+    }
+}
+
+#[async_trait]
+impl<E> Collector<E> for FileTailCollector<E>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "FileTailCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        let (file, mut position) = match File::open(&self.path).await {
+            Ok(mut file) => {
+                let position = match self.from_start {
+                    true => 0,
+                    false => file.seek(SeekFrom::End(0)).await?,
+                };
+                (Some(file), position)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                warn!(path = ?self.path, "file doesn't exist yet, waiting for it");
+                (None, 0)
+            }
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("fail to open file {:?}", self.path))
+            }
+        };
+
+        let stream = async_stream::stream! {
+            let file = match file {
+                Some(file) => file,
+                None => self.wait_for_file().await,
+            };
+
+            let mut reader = BufReader::new(file);
+            let mut line = String::new();
+
+            loop {
+                let read = match reader.read_line(&mut line).await {
+                    Ok(v) => v,
+                    Err(err) => {
+                        error!(path = ?self.path, "fail to read file: {err:#}");
+                        tokio::time::sleep(self.poll_interval).await;
+                        continue;
+                    }
+                };
+
+                position += read as u64;
+
+                // a partially written line is completed by a later read
+                if line.ends_with('\n') {
+                    if let Some(event) = self.errors.parse("FileTailCollector", &line) {
+                        yield event;
+                    }
+
+                    line.clear();
+                    continue;
+                }
+
+                if read > 0 {
+                    continue;
+                }
+
+                tokio::time::sleep(self.poll_interval).await;
+
+                if !self.is_rotated(reader.get_ref(), position).await {
+                    continue;
+                }
+
+                match File::open(&self.path).await {
+                    Ok(file) => {
+                        warn!(path = ?self.path, "file rotated, reopening");
+                        reader = BufReader::new(file);
+                        position = 0;
+                        line.clear();
+                    }
+                    Err(err) => error!(path = ?self.path, "fail to reopen rotated file: {err:#}"),
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(unix)]
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+#[cfg(unix)]
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Remove the socket file left behind by a previous run, unless another process is still listening on it
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> eyre::Result<()> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+
+    let is_socket = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket());
+    if !is_socket {
+        return Ok(());
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        eyre::bail!("unix socket {path:?} is already in use");
+    }
+
+    std::fs::remove_file(path)
+        .wrap_err_with(|| format!("fail to remove stale unix socket {path:?}"))
+}
+
+/// Listens on a unix domain socket and reads newline-delimited JSON events from every connection. A socket file left
+/// behind by a previous run is replaced. Connections stop being read while `buffer_size` lines wait for the engine, so
+/// a writer faster than the strategies is slowed down rather than buffered without bound.
+#[cfg(unix)]
+pub struct UnixSocketCollector<E> {
+    path: PathBuf,
+    buffer_size: usize,
+    errors: ErrorReporter,
+    _phantom: PhantomData<fn() -> E>,
+}
+
+#[cfg(unix)]
+impl<E> UnixSocketCollector<E> {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            buffer_size: 1024,
+            errors: ErrorReporter::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// How many lines read from the connections may wait for the engine, 1024 by default
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size.max(1);
+        self
+    }
+
+    pub fn with_error_sender(mut self, sender: UnboundedSender<LineError>) -> Self {
+        self.errors = ErrorReporter(Some(sender));
+        self
+    }
+}
+
+#[cfg(unix)]
+#[async_trait]
+impl<E> Collector<E> for UnixSocketCollector<E>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "UnixSocketCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        use tokio::{net::UnixListener, sync::mpsc};
+
+        remove_stale_socket(&self.path)?;
+
+        let listener = UnixListener::bind(&self.path)
+            .wrap_err_with(|| format!("fail to bind unix socket {:?}", self.path))?;
+
+        let (sender, mut receiver) = mpsc::channel::<String>(self.buffer_size);
+
+        tokio::spawn(async move {
+            let mut backoff = MIN_ACCEPT_BACKOFF;
+
+            while !sender.is_closed() {
+                let (connection, _) = match listener.accept().await {
+                    Ok(v) => {
+                        backoff = MIN_ACCEPT_BACKOFF;
+                        v
+                    }
+                    Err(err) => {
+                        // e.g. out of file descriptors, retrying right away would only spin
+                        error!("fail to accept unix socket connection: {err:#}");
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_ACCEPT_BACKOFF);
+                        continue;
+                    }
+                };
+
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(connection).lines();
+
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if sender.send(line).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let stream = async_stream::stream! {
+            while let Some(line) = receiver.recv().await {
+                if let Some(event) = self.errors.parse("UnixSocketCollector", &line) {
+                    yield event;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("line-collector-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    fn tail(path: &Path) -> FileTailCollector<Value> {
+        FileTailCollector::new(path)
+            .from_start()
+            .with_poll_interval(Duration::from_millis(10))
+    }
+
+    async fn next(stream: &mut CollectorStream<'_, Value>) -> Value {
+        tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .expect("no event")
+            .expect("stream ended")
+    }
+
+    #[test]
+    fn reports_malformed_lines() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let errors = ErrorReporter(Some(sender));
+
+        assert_eq!(
+            errors.parse::<Value>("test", " {\"a\": 1} \n"),
+            Some(json!({ "a": 1 }))
+        );
+        assert_eq!(errors.parse::<Value>("test", "  \n"), None);
+        assert!(receiver.try_recv().is_err());
+
+        assert_eq!(errors.parse::<Value>("test", "{\"a\":\n"), None);
+        let report = receiver.try_recv().unwrap();
+        assert_eq!(report.source, "test");
+        assert_eq!(report.line, "{\"a\":");
+        assert!(!report.error.is_empty());
+    }
+
+    #[tokio::test]
+    async fn completes_partial_lines() {
+        let path = temp_path("partial");
+        append(&path, "{\"id\": 1}\n{\"id\":");
+
+        let collector = tail(&path);
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 1 }));
+
+        let writer = {
+            let path = path.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                append(&path, " 2}\n");
+            })
+        };
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 2 }));
+        writer.await.unwrap();
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn reopens_truncated_files() {
+        let path = temp_path("truncated");
+        append(&path, "{\"id\": 1}\n{\"id\": 2}\n");
+
+        let collector = tail(&path);
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 1 }));
+        assert_eq!(next(&mut stream).await, json!({ "id": 2 }));
+
+        std::fs::write(&path, "{\"id\": 3}\n").unwrap();
+        assert_eq!(next(&mut stream).await, json!({ "id": 3 }));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reopens_renamed_files() {
+        let path = temp_path("renamed");
+        let rotated = path.with_extension("1");
+        append(&path, "{\"id\": 1}\n");
+
+        let collector = tail(&path);
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 1 }));
+
+        std::fs::rename(&path, &rotated).unwrap();
+        append(&path, "{\"id\": 2}\n{\"id\": 3}\n");
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 2 }));
+        assert_eq!(next(&mut stream).await, json!({ "id": 3 }));
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_file_to_exist() {
+        let path = temp_path("missing");
+
+        let collector =
+            FileTailCollector::<Value>::new(&path).with_poll_interval(Duration::from_millis(10));
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        append(&path, "{\"id\": 1}\n");
+        assert_eq!(next(&mut stream).await, json!({ "id": 1 }));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reads_lines_from_socket_connections() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::UnixStream;
+
+        let path = temp_path("socket");
+        let (sender, mut errors) = mpsc::unbounded_channel();
+
+        let collector = UnixSocketCollector::<Value>::new(&path)
+            .with_buffer_size(1)
+            .with_error_sender(sender);
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        let mut connection = UnixStream::connect(&path).await.unwrap();
+        connection
+            .write_all(b"{\"id\": 1}\nnot json\n{\"id\": 2}\n")
+            .await
+            .unwrap();
+
+        assert_eq!(next(&mut stream).await, json!({ "id": 1 }));
+        assert_eq!(next(&mut stream).await, json!({ "id": 2 }));
+        assert_eq!(errors.recv().await.unwrap().line, "not json");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
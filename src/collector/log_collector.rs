@@ -1,22 +1,199 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use alloy::{
+    primitives::BlockHash,
     providers::Provider,
-    rpc::types::eth::{Filter, Log},
+    rpc::types::{
+        eth::{Filter, Log},
+        Header,
+    },
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use tracing::{error, warn};
 
+use crate::collector::FailoverProvider;
 use crate::types::{Collector, CollectorStream};
 
 pub struct LogCollector {
     provider: Arc<dyn Provider>,
     filter: Filter,
+    failover: Option<Arc<FailoverProvider>>,
+    confirmations: u64,
+}
+
+struct BufferedBlock {
+    hash: BlockHash,
+    logs: Vec<Log>,
 }
 
 impl LogCollector {
     pub fn new(provider: Arc<dyn Provider>, filter: Filter) -> Self {
-        Self { provider, filter }
+        Self {
+            provider,
+            filter,
+            failover: None,
+            confirmations: 0,
+        }
+    }
+
+    /// Create a new `LogCollector` that holds back the logs of each block until the chain is `confirmations` blocks
+    /// ahead of it. Logs of blocks that are reorged out before that are never emitted.
+    pub fn new_with_confirmations(
+        provider: Arc<dyn Provider>,
+        filter: Filter,
+        confirmations: u64,
+    ) -> Self {
+        Self {
+            provider,
+            filter,
+            failover: None,
+            confirmations,
+        }
+    }
+
+    /// Create a new `LogCollector` that follows the active endpoint of `failover`, optionally waiting for
+    /// confirmations with [`Self::with_confirmations`]
+    pub fn new_with_failover(failover: Arc<FailoverProvider>, filter: Filter) -> Self {
+        Self {
+            provider: failover.active(),
+            filter,
+            failover: Some(failover),
+            confirmations: 0,
+        }
+    }
+
+    /// Hold back the logs of each block until the chain is `confirmations` blocks ahead of it, see
+    /// [`Self::new_with_confirmations`]. Combined with failover, blocks are checked and fetched through the endpoint
+    /// that is active at the time.
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    async fn confirmed_log_stream(&self) -> eyre::Result<CollectorStream<'_, Log>> {
+        let mut heads: CollectorStream<'_, Header> = match &self.failover {
+            Some(failover) => failover.subscribe(|provider| async move {
+                let stream = provider.subscribe_blocks().await?;
+                Ok(Box::pin(stream.into_stream()) as CollectorStream<'_, Header>)
+            }),
+            None => Box::pin(self.provider.subscribe_blocks().await?.into_stream()),
+        };
+
+        let stream = async_stream::stream! {
+            let mut buffer = ConfirmationBuffer::new(self.confirmations);
+
+            while let Some(header) = heads.next().await {
+                let provider = match &self.failover {
+                    Some(failover) => failover.active(),
+                    None => self.provider.clone(),
+                };
+
+                for log in buffer.on_head(provider.as_ref(), &self.filter, &header).await {
+                    yield log;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Logs of recent blocks, held back until the block is deep enough. A block is only released after checking that it
+/// is still the canonical block at its height, so logs of orphaned blocks are never released, and a block that can't
+/// be checked or fetched is retried on the next head rather than skipped.
+struct ConfirmationBuffer {
+    confirmations: u64,
+    blocks: BTreeMap<u64, BufferedBlock>,
+    /// The next block to release, set by the first head
+    next: Option<u64>,
+}
+
+impl ConfirmationBuffer {
+    fn new(confirmations: u64) -> Self {
+        Self {
+            confirmations,
+            blocks: BTreeMap::new(),
+            next: None,
+        }
+    }
+
+    /// Buffer the logs of `head` and return the logs of the blocks that are now confirmed, oldest first
+    async fn on_head(
+        &mut self,
+        provider: &dyn Provider,
+        filter: &Filter,
+        head: &Header,
+    ) -> Vec<Log> {
+        if let Some(logs) = block_to_logs(provider, filter, head.hash).await {
+            self.blocks.insert(
+                head.number,
+                BufferedBlock {
+                    hash: head.hash,
+                    logs,
+                },
+            );
+        }
+
+        let next = self.next.get_or_insert(head.number);
+        let mut released = Vec::new();
+
+        while *next + self.confirmations <= head.number {
+            let number = *next;
+
+            let hash = match provider.get_block_by_number(number.into()).await {
+                Ok(Some(block)) => block.header.hash,
+                Ok(None) => {
+                    warn!(
+                        block = number,
+                        "canonical block not found, retrying on next head"
+                    );
+                    break;
+                }
+                Err(err) => {
+                    error!(
+                        block = number,
+                        "fail to get canonical block, retrying on next head: {err:#}"
+                    );
+                    break;
+                }
+            };
+
+            // the buffered block was reorged out, or its head was never seen
+            let logs = match self.blocks.remove(&number) {
+                Some(block) if block.hash == hash => block.logs,
+                _ => match block_to_logs(provider, filter, hash).await {
+                    Some(logs) => logs,
+                    None => break,
+                },
+            };
+
+            released.extend(logs);
+            *next += 1;
+        }
+
+        // blocks of reorged heads below the released range
+        self.blocks = self.blocks.split_off(next);
+
+        released
+    }
+}
+
+async fn block_to_logs(
+    provider: &dyn Provider,
+    filter: &Filter,
+    block_hash: BlockHash,
+) -> Option<Vec<Log>> {
+    match provider
+        .get_logs(&filter.clone().at_block_hash(block_hash))
+        .await
+    {
+        Ok(logs) => Some(logs),
+        Err(err) => {
+            error!(?block_hash, "fail to get logs: {err:#}");
+            None
+        }
     }
 }
 
@@ -27,8 +204,182 @@ impl Collector<Log> for LogCollector {
     }
 
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Log>> {
+        if self.confirmations > 0 {
+            return self.confirmed_log_stream().await;
+        }
+
+        if let Some(failover) = &self.failover {
+            let filter = &self.filter;
+
+            return Ok(failover.subscribe(move |provider| async move {
+                let stream = provider.subscribe_logs(filter).await?;
+                Ok(Box::pin(stream.into_stream()) as CollectorStream<'_, Log>)
+            }));
+        }
+
         let stream = self.provider.subscribe_logs(&self.filter).await?;
         let stream = stream.into_stream().filter_map(|v| async move { Some(v) });
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus,
+        primitives::{keccak256, B256},
+        providers::{mock::Asserter, ProviderBuilder},
+        rpc::types::Block,
+    };
+
+    use super::*;
+
+    const A: u8 = 0;
+    const B: u8 = 1;
+
+    fn hash(number: u64, fork: u8) -> B256 {
+        keccak256([fork, number as u8])
+    }
+
+    fn header(number: u64, fork: u8) -> Header {
+        Header {
+            hash: hash(number, fork),
+            inner: consensus::Header {
+                number,
+                ..Default::default()
+            },
+            total_difficulty: None,
+            size: None,
+        }
+    }
+
+    fn log(number: u64, fork: u8) -> Log {
+        Log {
+            block_hash: Some(hash(number, fork)),
+            block_number: Some(number),
+            ..Default::default()
+        }
+    }
+
+    fn push_logs(asserter: &Asserter, number: u64, fork: u8) {
+        asserter.push_success(&vec![log(number, fork)]);
+    }
+
+    fn push_block(asserter: &Asserter, number: u64, fork: u8) {
+        asserter.push_success(&Block::<alloy::rpc::types::Transaction>::empty(header(
+            number, fork,
+        )));
+    }
+
+    struct Chain {
+        asserter: Asserter,
+        provider: Box<dyn Provider>,
+        buffer: ConfirmationBuffer,
+    }
+
+    impl Chain {
+        fn new(confirmations: u64) -> Self {
+            let asserter = Asserter::new();
+            let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+
+            Self {
+                asserter,
+                provider: Box::new(provider),
+                buffer: ConfirmationBuffer::new(confirmations),
+            }
+        }
+
+        async fn head(&mut self, number: u64, fork: u8) -> Vec<Log> {
+            self.buffer
+                .on_head(
+                    self.provider.as_ref(),
+                    &Filter::new(),
+                    &header(number, fork),
+                )
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn releases_blocks_once_confirmed() {
+        let mut chain = Chain::new(2);
+
+        push_logs(&chain.asserter, 100, A);
+        assert!(chain.head(100, A).await.is_empty());
+
+        push_logs(&chain.asserter, 101, A);
+        assert!(chain.head(101, A).await.is_empty());
+
+        // the buffered logs are released once the block is checked to be canonical
+        push_logs(&chain.asserter, 102, A);
+        push_block(&chain.asserter, 100, A);
+        assert_eq!(chain.head(102, A).await, vec![log(100, A)]);
+    }
+
+    #[tokio::test]
+    async fn replaces_orphaned_blocks() {
+        let mut chain = Chain::new(1);
+
+        push_logs(&chain.asserter, 100, A);
+        chain.head(100, A).await;
+
+        push_logs(&chain.asserter, 101, A);
+        push_block(&chain.asserter, 100, A);
+        assert_eq!(chain.head(101, A).await, vec![log(100, A)]);
+
+        // 101 was reorged out, the logs of the canonical block are fetched instead
+        push_logs(&chain.asserter, 102, B);
+        push_block(&chain.asserter, 101, B);
+        push_logs(&chain.asserter, 101, B);
+        assert_eq!(chain.head(102, B).await, vec![log(101, B)]);
+    }
+
+    #[tokio::test]
+    async fn fills_gaps() {
+        let mut chain = Chain::new(1);
+
+        push_logs(&chain.asserter, 100, A);
+        chain.head(100, A).await;
+
+        push_logs(&chain.asserter, 103, A);
+        push_block(&chain.asserter, 100, A);
+        push_block(&chain.asserter, 101, A);
+        push_logs(&chain.asserter, 101, A);
+        push_block(&chain.asserter, 102, A);
+        push_logs(&chain.asserter, 102, A);
+        assert_eq!(
+            chain.head(103, A).await,
+            vec![log(100, A), log(101, A), log(102, A)]
+        );
+    }
+
+    #[tokio::test]
+    async fn holds_blocks_that_cannot_be_checked() {
+        let mut chain = Chain::new(1);
+
+        push_logs(&chain.asserter, 100, A);
+        chain.head(100, A).await;
+
+        push_logs(&chain.asserter, 101, A);
+        chain.asserter.push_failure_msg("connection reset");
+        assert!(chain.head(101, A).await.is_empty());
+
+        // 100 was reorged out, but the logs of the canonical block can't be fetched yet
+        push_logs(&chain.asserter, 102, B);
+        push_block(&chain.asserter, 100, B);
+        chain.asserter.push_failure_msg("connection reset");
+        assert!(chain.head(102, B).await.is_empty());
+
+        // the orphaned logs of 100 are never released
+        chain.asserter.push_failure_msg("connection reset");
+        push_block(&chain.asserter, 100, B);
+        push_logs(&chain.asserter, 100, B);
+        push_block(&chain.asserter, 101, B);
+        push_logs(&chain.asserter, 101, B);
+        push_block(&chain.asserter, 102, B);
+        assert_eq!(
+            chain.head(103, B).await,
+            vec![log(100, B), log(101, B), log(102, B)]
+        );
+    }
+}
@@ -23,7 +23,7 @@ impl LogsInBlockCollector {
         Self { provider, filter }
     }
 
-    async fn block_to_logs(&self, block_hash: BlockHash) -> Option<Vec<Log>> {
+    pub(crate) async fn block_to_logs(&self, block_hash: BlockHash) -> Option<Vec<Log>> {
         let logs = self
             .provider
             .get_logs(&self.filter.clone().at_block_hash(block_hash))
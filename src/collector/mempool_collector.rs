@@ -1,5 +1,7 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use crate::collector::FailoverProvider;
 use crate::types::{Collector, CollectorStream};
 use alloy::transports::{RpcError, TransportErrorKind};
 use alloy::{primitives::B256, providers::Provider, rpc::types::eth::Transaction};
@@ -13,15 +15,91 @@ use std::{
     pin::Pin,
     task::{Context, Poll},
 };
-use tracing::error;
 
 pub struct MempoolCollector {
     provider: Arc<dyn Provider>,
+    failover: Option<Arc<FailoverProvider>>,
+    max_concurrent: usize,
+    buffer_limit: Option<(usize, DropPolicy)>,
+    dropped: Arc<AtomicU64>,
 }
 
 impl MempoolCollector {
     pub fn new(provider: Arc<dyn Provider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            failover: None,
+            max_concurrent: 256,
+            buffer_limit: None,
+            dropped: Default::default(),
+        }
+    }
+
+    pub fn new_with_failover(failover: Arc<FailoverProvider>) -> Self {
+        Self {
+            failover: Some(failover.clone()),
+            ..Self::new(failover.active())
+        }
+    }
+
+    /// Fetch at most `max_concurrent` transactions at once, at least one
+    pub fn with_concurrency(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Buffer at most `max_buffered` hashes waiting to be fetched, and drop hashes according to `policy` beyond that
+    pub fn with_buffer_limit(mut self, max_buffered: usize, policy: DropPolicy) -> Self {
+        self.buffer_limit = Some((max_buffered, policy));
+        self
+    }
+
+    /// How many pending transaction hashes were dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// The counter behind [`Self::dropped`], to keep reading it once the collector is handed to the engine
+    pub fn dropped_counter(&self) -> Arc<AtomicU64> {
+        self.dropped.clone()
+    }
+
+    /// Hashes of pending transactions as soon as they're announced, without fetching the transactions
+    pub(crate) async fn get_hash_stream(&self) -> eyre::Result<CollectorStream<'_, B256>> {
+        if let Some(failover) = &self.failover {
+            return Ok(failover.subscribe(move |provider| async move {
+                let stream = provider
+                    .subscribe_pending_transactions()
+                    .await
+                    .wrap_err("fail to subscribe to pending transaction stream")?
+                    .into_stream();
+
+                Ok(Box::pin(stream) as CollectorStream<'_, B256>)
+            }));
+        }
+
+        let stream = self
+            .provider
+            .subscribe_pending_transactions()
+            .await
+            .wrap_err("fail to subscribe to pending transaction stream")?
+            .into_stream();
+
+        Ok(Box::pin(stream))
+    }
+
+    fn transaction_stream<'a, St>(
+        &self,
+        provider: &'a dyn Provider,
+        stream: St,
+    ) -> TransactionStream<'a, St> {
+        let stream = TransactionStream::new(provider, stream, self.max_concurrent)
+            .with_dropped_counter(self.dropped.clone());
+
+        match self.buffer_limit {
+            Some((max_buffered, policy)) => stream.with_buffer_limit(max_buffered, policy),
+            None => stream,
+        }
     }
 }
 
@@ -32,6 +110,21 @@ impl Collector<Transaction> for MempoolCollector {
     }
 
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Transaction>> {
+        if let Some(failover) = &self.failover {
+            return Ok(failover.subscribe(move |provider| async move {
+                let stream = provider
+                    .subscribe_pending_transactions()
+                    .await
+                    .wrap_err("fail to subscribe to pending transaction stream")?
+                    .into_stream();
+
+                let stream = self.transaction_stream(provider, stream);
+                let stream = stream.filter_map(|res| async move { res.ok() });
+
+                Ok(Box::pin(stream) as CollectorStream<'_, Transaction>)
+            }));
+        }
+
         let stream = self
             .provider
             .subscribe_pending_transactions()
@@ -39,7 +132,7 @@ impl Collector<Transaction> for MempoolCollector {
             .wrap_err("fail to subscribe to pending transaction stream")?
             .into_stream();
 
-        let stream = TransactionStream::new(self.provider.as_ref(), stream, 256);
+        let stream = self.transaction_stream(self.provider.as_ref(), stream);
         let stream = stream.filter_map(|res| async move { res.ok() });
 
         Ok(Box::pin(stream))
@@ -61,6 +154,17 @@ pub(crate) type TransactionFut<'a> = Pin<Box<dyn Future<Output = TransactionResu
 
 pub(crate) type TransactionResult = Result<Transaction, GetTransactionError>;
 
+/// Which hashes `TransactionStream` drops once its buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Drop the oldest buffered hash to make room for the new one
+    DropOldest,
+    /// Drop the new hash
+    DropNewest,
+    /// Keep one in every `n` new hashes, in place of the oldest buffered hash, and drop the others
+    Sample(usize),
+}
+
 /// Drains a stream of transaction hashes and yields entire `Transaction`.
 #[must_use = "streams do nothing unless polled"]
 pub struct TransactionStream<'a, St> {
@@ -76,10 +180,16 @@ pub struct TransactionStream<'a, St> {
     stream_done: bool,
     /// max allowed futures to execute at once.
     pub(crate) max_concurrent: usize,
+    /// max buffered hashes, and what to drop beyond that
+    pub(crate) buffer_limit: Option<(usize, DropPolicy)>,
+    /// hashes seen while the buffer was full, for sampling
+    overflowed: usize,
+    /// number of hashes dropped because the buffer was full
+    dropped: Arc<AtomicU64>,
 }
 
 impl<'a, St> TransactionStream<'a, St> {
-    /// Create a new `TransactionStream` instance
+    /// Create a new `TransactionStream` instance, fetching at most `max_concurrent` transactions at once (at least one)
     pub fn new(provider: &'a dyn Provider, stream: St, max_concurrent: usize) -> Self {
         Self {
             pending: Default::default(),
@@ -87,7 +197,60 @@ impl<'a, St> TransactionStream<'a, St> {
             provider,
             stream,
             stream_done: false,
-            max_concurrent,
+            max_concurrent: max_concurrent.max(1),
+            buffer_limit: None,
+            overflowed: 0,
+            dropped: Default::default(),
+        }
+    }
+
+    /// Buffer at most `max_buffered` hashes waiting for a free fetch slot, and drop hashes according to `policy`
+    /// beyond that
+    pub fn with_buffer_limit(mut self, max_buffered: usize, policy: DropPolicy) -> Self {
+        self.buffer_limit = Some((max_buffered, policy));
+        self
+    }
+
+    /// Count dropped hashes in `counter`, e.g. to share it across streams
+    pub fn with_dropped_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.dropped = counter;
+        self
+    }
+
+    /// How many hashes were dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Buffer a hash waiting for a free fetch slot, applying the buffer limit
+    pub(crate) fn buffer_tx(&mut self, tx: B256) {
+        let (max_buffered, policy) = match self.buffer_limit {
+            Some(limit) if self.buffered.len() >= limit.0 => limit,
+            _ => {
+                self.overflowed = 0;
+                self.buffered.push_back(tx);
+                return;
+            }
+        };
+
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+
+        let keep = match policy {
+            DropPolicy::DropOldest => true,
+            DropPolicy::DropNewest => false,
+            DropPolicy::Sample(n) => {
+                self.overflowed += 1;
+                self.overflowed % n.max(1) == 0
+            }
+        };
+
+        if keep {
+            if self.buffered.len() >= max_buffered {
+                self.buffered.pop_front();
+            }
+            if max_buffered > 0 {
+                self.buffered.push_back(tx);
+            }
         }
     }
 
@@ -131,7 +294,7 @@ where
                         if this.pending.len() < this.max_concurrent {
                             this.push_tx(tx);
                         } else {
-                            this.buffered.push_back(tx);
+                            this.buffer_tx(tx);
                         }
                     }
                     Poll::Ready(None) => {
@@ -156,3 +319,135 @@ where
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::providers::{mock::Asserter, ProviderBuilder, RootProvider};
+    use futures::stream::{empty, Empty};
+
+    use super::*;
+
+    fn provider() -> RootProvider {
+        ProviderBuilder::default().connect_mocked_client(Asserter::new())
+    }
+
+    fn hash(n: u8) -> B256 {
+        B256::repeat_byte(n)
+    }
+
+    fn buffer(
+        stream: &mut TransactionStream<'_, Empty<B256>>,
+        hashes: impl IntoIterator<Item = u8>,
+    ) {
+        for n in hashes {
+            stream.buffer_tx(hash(n));
+        }
+    }
+
+    fn buffered(stream: &TransactionStream<'_, Empty<B256>>) -> Vec<B256> {
+        stream.buffered.iter().copied().collect()
+    }
+
+    #[test]
+    fn buffers_everything_without_limit() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1);
+
+        buffer(&mut stream, 1..=5);
+
+        assert_eq!(buffered(&stream), (1..=5).map(hash).collect::<Vec<_>>());
+        assert_eq!(stream.dropped(), 0);
+    }
+
+    #[test]
+    fn drops_oldest_hashes() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1)
+            .with_buffer_limit(2, DropPolicy::DropOldest);
+
+        buffer(&mut stream, 1..=4);
+
+        assert_eq!(buffered(&stream), vec![hash(3), hash(4)]);
+        assert_eq!(stream.dropped(), 2);
+    }
+
+    #[test]
+    fn drops_newest_hashes() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1)
+            .with_buffer_limit(2, DropPolicy::DropNewest);
+
+        buffer(&mut stream, 1..=4);
+
+        assert_eq!(buffered(&stream), vec![hash(1), hash(2)]);
+        assert_eq!(stream.dropped(), 2);
+    }
+
+    #[test]
+    fn samples_hashes_beyond_the_limit() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1)
+            .with_buffer_limit(2, DropPolicy::Sample(3));
+
+        buffer(&mut stream, 1..=8);
+
+        // 5 and 8 are the 3rd and 6th hashes over the limit, each replacing the oldest buffered one
+        assert_eq!(buffered(&stream), vec![hash(5), hash(8)]);
+        assert_eq!(stream.dropped(), 6);
+    }
+
+    #[test]
+    fn sampling_starts_over_once_the_buffer_has_room() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1)
+            .with_buffer_limit(1, DropPolicy::Sample(2));
+
+        buffer(&mut stream, [1, 2]);
+        stream.buffered.clear();
+        buffer(&mut stream, [3, 4]);
+
+        assert_eq!(buffered(&stream), vec![hash(3)]);
+    }
+
+    #[test]
+    fn buffers_nothing_with_a_zero_limit() {
+        let provider = provider();
+        let mut stream = TransactionStream::new(&provider, empty(), 1)
+            .with_buffer_limit(0, DropPolicy::DropOldest);
+
+        buffer(&mut stream, 1..=3);
+
+        assert!(stream.buffered.is_empty());
+        assert_eq!(stream.dropped(), 3);
+    }
+
+    #[test]
+    fn shares_the_dropped_counter() {
+        let provider = provider();
+        let collector = MempoolCollector::new(Arc::new(provider.clone()))
+            .with_buffer_limit(1, DropPolicy::DropNewest);
+        let counter = collector.dropped_counter();
+
+        let mut stream = collector.transaction_stream(&provider, empty::<B256>());
+        buffer(&mut stream, 1..=3);
+
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+        assert_eq!(collector.dropped(), 2);
+    }
+
+    #[test]
+    fn fetches_at_least_one_transaction_at_a_time() {
+        let provider = provider();
+
+        assert_eq!(
+            TransactionStream::new(&provider, empty::<B256>(), 0).max_concurrent,
+            1
+        );
+        assert_eq!(
+            MempoolCollector::new(Arc::new(provider))
+                .with_concurrency(0)
+                .max_concurrent,
+            1
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use alloy::{
+    primitives::{BlockHash, TxHash},
+    providers::Provider,
+    rpc::types::eth::Block,
+};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+
+use crate::collector::{FullBlockCollector, MempoolCollector};
+use crate::types::{Collector, CollectorStream};
+
+/// How many blocks of included hashes are remembered, so transactions announced in the mempool after their block
+/// arrived are not counted as pending
+const RECENT_BLOCKS: usize = 8;
+
+/// A transaction of the block that was seen in the mempool first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludedTransaction {
+    pub hash: TxHash,
+    pub first_seen: SystemTime,
+    /// From the transaction being first seen in the mempool to the block including it arriving
+    pub latency: Duration,
+}
+
+/// Which transactions of a block went through the mempool.
+#[derive(Debug, Clone)]
+pub struct InclusionReport {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub received_at: SystemTime,
+    /// Transactions that were seen in the mempool before the block arrived
+    pub included: Vec<IncludedTransaction>,
+    /// Transactions that were never seen in the mempool
+    pub private: Vec<TxHash>,
+    /// How many transactions seen in the mempool are still pending after the block
+    pub still_pending: usize,
+}
+
+impl InclusionReport {
+    pub fn transaction_count(&self) -> usize {
+        self.included.len() + self.private.len()
+    }
+
+    /// The share of the block's transactions that were never seen in the mempool, between 0 and 1
+    pub fn private_ratio(&self) -> f64 {
+        match self.transaction_count() {
+            0 => 0.0,
+            total => self.private.len() as f64 / total as f64,
+        }
+    }
+}
+
+enum Input {
+    Pending(TxHash),
+    Block(Box<Block>),
+}
+
+/// Combines `MempoolCollector` and `FullBlockCollector`, and reports for every block which of its transactions were
+/// seen in the mempool and how long they waited. Transactions are timed from their hash being announced, they are
+/// not fetched.
+pub struct MempoolInclusionCollector {
+    mempool: MempoolCollector,
+    blocks: FullBlockCollector,
+    pending_ttl: Duration,
+}
+
+impl MempoolInclusionCollector {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self::new_with_collectors(
+            MempoolCollector::new(provider.clone()),
+            FullBlockCollector::new(provider),
+        )
+    }
+
+    pub fn new_with_collectors(mempool: MempoolCollector, blocks: FullBlockCollector) -> Self {
+        Self {
+            mempool,
+            blocks,
+            pending_ttl: Duration::from_secs(600),
+        }
+    }
+
+    /// Forget pending transactions that were not included within `pending_ttl`, e.g. because they were replaced
+    pub fn with_pending_ttl(mut self, pending_ttl: Duration) -> Self {
+        self.pending_ttl = pending_ttl;
+        self
+    }
+}
+
+#[async_trait]
+impl Collector<InclusionReport> for MempoolInclusionCollector {
+    fn name(&self) -> &str {
+        "MempoolInclusionCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, InclusionReport>> {
+        let pending = self.mempool.get_hash_stream().await?;
+        let blocks = self.blocks.get_event_stream().await?;
+
+        let mut inputs = stream::select(
+            pending.map(Input::Pending),
+            blocks.map(|block| Input::Block(Box::new(block))),
+        );
+
+        let stream = async_stream::stream! {
+            let mut first_seen = HashMap::<TxHash, (Instant, SystemTime)>::new();
+            let mut recent = VecDeque::<HashSet<TxHash>>::with_capacity(RECENT_BLOCKS);
+
+            while let Some(input) = inputs.next().await {
+                let block = match input {
+                    Input::Pending(hash) => {
+                        if !recent.iter().any(|hashes| hashes.contains(&hash)) {
+                            first_seen.entry(hash).or_insert_with(|| (Instant::now(), SystemTime::now()));
+                        }
+                        continue;
+                    }
+                    Input::Block(block) => block,
+                };
+
+                let now = Instant::now();
+                let mut included = vec![];
+                let mut private = vec![];
+
+                for hash in block.transactions.hashes() {
+                    match first_seen.remove(&hash) {
+                        Some((seen, seen_at)) => included.push(IncludedTransaction {
+                            hash,
+                            first_seen: seen_at,
+                            latency: now - seen,
+                        }),
+                        None => private.push(hash),
+                    }
+                }
+
+                first_seen.retain(|_, (seen, _)| now - *seen < self.pending_ttl);
+
+                if recent.len() == RECENT_BLOCKS {
+                    recent.pop_front();
+                }
+                recent.push_back(block.transactions.hashes().collect());
+
+                yield InclusionReport {
+                    block_number: block.header.number,
+                    block_hash: block.header.hash,
+                    received_at: SystemTime::now(),
+                    included,
+                    private,
+                    still_pending: first_seen.len(),
+                };
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -1,28 +1,98 @@
 #[cfg(feature = "ethereum")]
 mod block_collector;
 #[cfg(feature = "ethereum")]
+mod call_trace_collector;
+#[cfg(feature = "ethereum")]
+mod decoded_log_collector;
+#[cfg(feature = "ethereum")]
+mod failover;
+#[cfg(feature = "ethereum")]
+mod finality_collector;
+#[cfg(feature = "ethereum")]
 mod full_block_collector;
 #[cfg(feature = "ethereum")]
+mod head_lag_collector;
+#[cfg(feature = "ethereum")]
 mod log_collector;
 #[cfg(feature = "ethereum")]
 mod logs_in_block_collector;
 #[cfg(feature = "ethereum")]
 mod mempool_collector;
 #[cfg(feature = "ethereum")]
+mod mempool_inclusion_collector;
+#[cfg(feature = "ethereum")]
+mod multicall_collector;
+#[cfg(feature = "ethereum")]
+mod pending_call_trace_collector;
+#[cfg(feature = "ethereum")]
+mod pending_pool_collector;
+#[cfg(feature = "ethereum")]
 mod poll_full_block_collector;
+#[cfg(feature = "ethereum")]
+mod storage_watch_collector;
 
 #[cfg(feature = "ethereum")]
 pub use block_collector::BlockCollector;
 #[cfg(feature = "ethereum")]
+pub use call_trace_collector::{BlockTracer, BlockTraces, CallTraceCollector};
+#[cfg(feature = "ethereum")]
+pub use decoded_log_collector::{DecodedLogCollector, LogDecodeError, LogMeta};
+#[cfg(feature = "ethereum")]
+pub use failover::FailoverProvider;
+#[cfg(feature = "ethereum")]
+pub use finality_collector::{ConfirmationLevel, FinalityCollector, FinalityEvent};
+#[cfg(feature = "ethereum")]
 pub use full_block_collector::FullBlockCollector;
 #[cfg(feature = "ethereum")]
+pub use head_lag_collector::{HeadLagCollector, HeadLagEvent};
+#[cfg(feature = "ethereum")]
 pub use log_collector::LogCollector;
 #[cfg(feature = "ethereum")]
 pub use logs_in_block_collector::LogsInBlockCollector;
 #[cfg(feature = "ethereum")]
-pub use mempool_collector::MempoolCollector;
+pub use mempool_collector::{DropPolicy, GetTransactionError, MempoolCollector, TransactionStream};
+#[cfg(feature = "ethereum")]
+pub use mempool_inclusion_collector::{
+    IncludedTransaction, InclusionReport, MempoolInclusionCollector,
+};
+#[cfg(feature = "ethereum")]
+pub use multicall_collector::{
+    CallOutcome, MulticallCollector, MulticallMode, MulticallResult, MulticallSnapshot,
+};
+#[cfg(feature = "ethereum")]
+pub use pending_call_trace_collector::PendingCallTraceCollector;
+#[cfg(feature = "ethereum")]
+pub use pending_pool_collector::{PendingPool, PendingPoolCollector, PoolEvent};
 #[cfg(feature = "ethereum")]
 pub use poll_full_block_collector::PollFullBlockCollector;
+#[cfg(feature = "ethereum")]
+pub use storage_watch_collector::{StorageWatchCollector, Watch, WatchChanges};
+
+#[cfg(feature = "cron")]
+mod schedule_collector;
+
+#[cfg(feature = "cron")]
+pub use schedule_collector::{ScheduleCollector, ScheduledTick, TickSchedule};
+
+#[cfg(feature = "jsonl")]
+mod line_collector;
+
+#[cfg(all(feature = "jsonl", unix))]
+pub use line_collector::UnixSocketCollector;
+#[cfg(feature = "jsonl")]
+pub use line_collector::{FileTailCollector, LineError, StdinCollector};
+
+#[cfg(feature = "telegram")]
+mod telegram_command_collector;
+
+#[cfg(feature = "telegram")]
+pub use telegram_command_collector::{CommandSource, TelegramCommand, TelegramCommandCollector};
+
+#[cfg(feature = "webhook")]
+mod webhook_collector;
+
+#[cfg(feature = "webhook")]
+pub use webhook_collector::{WebhookAuth, WebhookCollector};
 
 mod interval_collector;
-pub use interval_collector::IntervalCollector;
+pub use interval_collector::{IntervalCollector, MissedTickPolicy};
@@ -0,0 +1,368 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, Bytes},
+    providers::{
+        bindings::IMulticall3::{self, Call3},
+        Provider, MULTICALL3_ADDRESS,
+    },
+    rpc::types::eth::{BlockId, TransactionRequest},
+    sol_types::SolCall,
+};
+use async_trait::async_trait;
+use futures::{future::try_join_all, StreamExt};
+use tracing::error;
+
+use crate::types::{Collector, CollectorStream};
+
+/// What [`MulticallCollector`] emits for each block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MulticallMode {
+    /// Every call result, on every block
+    #[default]
+    Snapshot,
+    /// Only the calls whose result changed since the previous block
+    Changes,
+}
+
+#[derive(Debug, Clone)]
+pub enum CallOutcome<R> {
+    Success(R),
+    /// The call reverted or returned data that can't be decoded, with the raw return data
+    Failure(Bytes),
+}
+
+#[derive(Debug, Clone)]
+pub struct MulticallResult<R> {
+    /// Position of the call in the list the collector was created with
+    pub index: usize,
+    pub target: Address,
+    pub outcome: CallOutcome<R>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MulticallSnapshot<R> {
+    pub block_number: u64,
+    pub results: Vec<MulticallResult<R>>,
+}
+
+type Decoder<R> = Box<dyn Fn(&[u8]) -> alloy::sol_types::Result<R> + Send + Sync>;
+
+/// Polls a list of view calls on every new block, aggregated through Multicall3 `aggregate3` with `allowFailure` set,
+/// so a single failing call doesn't fail the whole batch. Calls of different functions are decoded into a common
+/// type `R`, see [`MulticallCollector::with_call`].
+pub struct MulticallCollector<R> {
+    provider: Arc<dyn Provider>,
+    calls: Vec<Call3>,
+    decoders: Vec<Decoder<R>>,
+    multicall_address: Address,
+    chunk_size: usize,
+    max_calldata: Option<usize>,
+    mode: MulticallMode,
+}
+
+impl<R: 'static> MulticallCollector<R> {
+    /// Create a new `MulticallCollector` polling calls of a single function
+    pub fn new<C>(provider: Arc<dyn Provider>, calls: Vec<(Address, C)>) -> Self
+    where
+        C: SolCall<Return = R> + 'static,
+    {
+        calls
+            .into_iter()
+            .fold(Self::new_empty(provider), |collector, (target, call)| {
+                collector.with_call(target, call, |ret| ret)
+            })
+    }
+
+    /// Create a new `MulticallCollector` without calls, to be added with [`Self::with_call`]
+    pub fn new_empty(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider,
+            calls: vec![],
+            decoders: vec![],
+            multicall_address: MULTICALL3_ADDRESS,
+            chunk_size: 500,
+            max_calldata: None,
+            mode: MulticallMode::Snapshot,
+        }
+    }
+
+    /// Poll `call` on `target`, and turn its return value into `R` with `map`, e.g. a variant of an enum covering
+    /// every polled function
+    pub fn with_call<C, F>(mut self, target: Address, call: C, map: F) -> Self
+    where
+        C: SolCall + 'static,
+        F: Fn(C::Return) -> R + Send + Sync + 'static,
+    {
+        self.calls.push(Call3 {
+            target,
+            allowFailure: true,
+            callData: call.abi_encode().into(),
+        });
+        self.decoders
+            .push(Box::new(move |data| C::abi_decode_returns(data).map(&map)));
+        self
+    }
+
+    /// Use a Multicall3 deployment other than the canonical one
+    pub fn with_multicall_address(mut self, address: Address) -> Self {
+        self.multicall_address = address;
+        self
+    }
+
+    /// Maximum number of calls aggregated into a single `eth_call`
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Maximum calldata bytes of the calls aggregated into a single `eth_call`, on top of the chunk size. A call
+    /// larger than that goes alone in its chunk.
+    pub fn with_max_calldata(mut self, max_calldata: usize) -> Self {
+        self.max_calldata = Some(max_calldata);
+        self
+    }
+
+    /// Split the calls into chunks within both the chunk size and the calldata budget
+    fn chunks(&self) -> Vec<Range<usize>> {
+        let mut chunks = vec![];
+        let mut start = 0;
+        let mut calldata = 0;
+
+        for (index, call) in self.calls.iter().enumerate() {
+            let size = call.callData.len();
+            let full = index - start >= self.chunk_size
+                || self
+                    .max_calldata
+                    .is_some_and(|max| index > start && calldata + size > max);
+
+            if full {
+                chunks.push(start..index);
+                start = index;
+                calldata = 0;
+            }
+
+            calldata += size;
+        }
+
+        if start < self.calls.len() {
+            chunks.push(start..self.calls.len());
+        }
+
+        chunks
+    }
+
+    pub fn with_mode(mut self, mode: MulticallMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    async fn aggregate(&self, block_number: u64) -> eyre::Result<Vec<IMulticall3::Result>> {
+        let chunks = self.chunks().into_iter().map(|range| async move {
+            let input = IMulticall3::aggregate3Call {
+                calls: self.calls[range].to_vec(),
+            };
+            let tx = TransactionRequest::default()
+                .to(self.multicall_address)
+                .input(input.abi_encode().into());
+
+            let data = self
+                .provider
+                .call(tx)
+                .block(BlockId::number(block_number))
+                .await?;
+
+            Ok::<_, eyre::Error>(IMulticall3::aggregate3Call::abi_decode_returns(&data)?)
+        });
+
+        let results = try_join_all(chunks).await?;
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    fn decode(&self, index: usize, result: &IMulticall3::Result) -> CallOutcome<R> {
+        if !result.success {
+            return CallOutcome::Failure(result.returnData.clone());
+        }
+
+        let target = self.calls[index].target;
+        match (self.decoders[index])(&result.returnData) {
+            Ok(value) => CallOutcome::Success(value),
+            Err(e) => {
+                error!(index, ?target, "fail to decode return data: {e:#}");
+                CallOutcome::Failure(result.returnData.clone())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<R: Send + Sync + 'static> Collector<MulticallSnapshot<R>> for MulticallCollector<R> {
+    fn name(&self) -> &str {
+        "MulticallCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, MulticallSnapshot<R>>> {
+        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
+
+        let stream = async_stream::stream! {
+            let mut last_results: Vec<Option<IMulticall3::Result>> = vec![None; self.calls.len()];
+
+            while let Some(block) = stream.next().await {
+                let results = match self.aggregate(block.number).await {
+                    Ok(results) => results,
+                    Err(e) => {
+                        error!(block = block.number, "fail to aggregate calls: {e:#}");
+                        continue;
+                    }
+                };
+
+                let mut snapshot = MulticallSnapshot {
+                    block_number: block.number,
+                    results: Vec::with_capacity(results.len()),
+                };
+
+                for (index, result) in results.into_iter().enumerate() {
+                    let changed = last_results[index].as_ref() != Some(&result);
+
+                    if changed || self.mode == MulticallMode::Snapshot {
+                        let target = self.calls[index].target;
+                        snapshot.results.push(MulticallResult {
+                            index,
+                            target,
+                            outcome: self.decode(index, &result),
+                        });
+                    }
+
+                    last_results[index] = Some(result);
+                }
+
+                if snapshot.results.is_empty() {
+                    continue;
+                }
+
+                yield snapshot;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::U256,
+        providers::{mock::Asserter, ProviderBuilder},
+        sol,
+    };
+
+    use super::*;
+
+    sol! {
+        function balanceOf(address owner) external view returns (uint256);
+        function name() external view returns (string);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Balance(U256),
+        Name(String),
+    }
+
+    fn collector() -> MulticallCollector<Value> {
+        let provider = ProviderBuilder::new().connect_mocked_client(Asserter::new());
+        MulticallCollector::new_empty(Arc::new(provider))
+    }
+
+    fn with_balances(collector: MulticallCollector<Value>, n: usize) -> MulticallCollector<Value> {
+        (0..n).fold(collector, |collector, _| {
+            collector.with_call(
+                Address::ZERO,
+                balanceOfCall {
+                    owner: Address::ZERO,
+                },
+                Value::Balance,
+            )
+        })
+    }
+
+    #[test]
+    fn chunks_by_call_count() {
+        let collector = with_balances(collector(), 5).with_chunk_size(2);
+
+        assert_eq!(collector.chunks(), vec![0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn chunks_by_calldata_size() {
+        // `balanceOf` calldata is 36 bytes
+        let collector = with_balances(collector(), 5).with_max_calldata(80);
+
+        assert_eq!(collector.chunks(), vec![0..2, 2..4, 4..5]);
+    }
+
+    #[test]
+    fn keeps_oversized_calls_in_their_own_chunk() {
+        let collector = with_balances(collector(), 3).with_max_calldata(10);
+
+        assert_eq!(collector.chunks(), vec![0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn has_no_chunk_without_calls() {
+        assert!(collector().chunks().is_empty());
+    }
+
+    #[test]
+    fn decodes_each_call_with_its_own_function() {
+        let collector = collector()
+            .with_call(
+                Address::ZERO,
+                balanceOfCall {
+                    owner: Address::ZERO,
+                },
+                Value::Balance,
+            )
+            .with_call(Address::ZERO, nameCall {}, Value::Name);
+
+        let balance = IMulticall3::Result {
+            success: true,
+            returnData: balanceOfCall::abi_encode_returns(&U256::from(7)).into(),
+        };
+        let name = IMulticall3::Result {
+            success: true,
+            returnData: nameCall::abi_encode_returns(&"token".to_string()).into(),
+        };
+
+        assert!(matches!(
+            collector.decode(0, &balance),
+            CallOutcome::Success(Value::Balance(v)) if v == U256::from(7)
+        ));
+        assert!(matches!(
+            collector.decode(1, &name),
+            CallOutcome::Success(Value::Name(v)) if v == "token"
+        ));
+
+        // data of another function doesn't decode
+        assert!(matches!(
+            collector.decode(1, &balance),
+            CallOutcome::Failure(_)
+        ));
+    }
+
+    #[test]
+    fn reports_reverted_calls_as_failures() {
+        let collector = with_balances(collector(), 1);
+        let reverted = IMulticall3::Result {
+            success: false,
+            returnData: Bytes::from_static(&[1, 2, 3]),
+        };
+
+        assert!(matches!(
+            collector.decode(0, &reverted),
+            CallOutcome::Failure(data) if data == reverted.returnData
+        ));
+    }
+}
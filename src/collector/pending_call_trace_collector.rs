@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use alloy::{
+    providers::Provider,
+    rpc::types::{
+        eth::{BlockId, Transaction},
+        trace::geth::{
+            CallConfig, CallFrame, GethDebugTracingCallOptions, GethDebugTracingOptions,
+        },
+    },
+};
+use async_trait::async_trait;
+use eyre::WrapErr;
+use futures::StreamExt;
+use tracing::error;
+
+use crate::collector::mempool_collector::TransactionStream;
+use crate::types::{Collector, CollectorStream};
+
+/// Traces every pending transaction with `debug_traceCall` and the built-in `callTracer` against the pending block.
+pub struct PendingCallTraceCollector {
+    provider: Arc<dyn Provider>,
+    config: CallConfig,
+    max_concurrent: usize,
+}
+
+impl PendingCallTraceCollector {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self::new_with_config(provider, CallConfig::default(), 64)
+    }
+
+    pub fn new_with_config(
+        provider: Arc<dyn Provider>,
+        config: CallConfig,
+        max_concurrent: usize,
+    ) -> Self {
+        Self {
+            provider,
+            config,
+            max_concurrent,
+        }
+    }
+
+    async fn trace_transaction(&self, tx: Transaction) -> Option<(Transaction, CallFrame)> {
+        let tx_hash = *tx.inner.tx_hash();
+        let options = GethDebugTracingCallOptions::default()
+            .with_tracing_options(GethDebugTracingOptions::call_tracer(self.config));
+
+        let frame = self
+            .provider
+            .root()
+            .raw_request::<_, CallFrame>(
+                "debug_traceCall".into(),
+                (tx.clone().into_request(), BlockId::pending(), options),
+            )
+            .await;
+
+        match frame {
+            Ok(frame) => Some((tx, frame)),
+            Err(e) => {
+                error!(tx = ?tx_hash, "fail to trace pending tx: {e:#}");
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Collector<(Transaction, CallFrame)> for PendingCallTraceCollector {
+    fn name(&self) -> &str {
+        "PendingCallTraceCollector"
+    }
+
+    async fn get_event_stream(
+        &self,
+    ) -> eyre::Result<CollectorStream<'_, (Transaction, CallFrame)>> {
+        let stream = self
+            .provider
+            .subscribe_pending_transactions()
+            .await
+            .wrap_err("fail to subscribe to pending transaction stream")?
+            .into_stream();
+
+        let stream = TransactionStream::new(self.provider.as_ref(), stream, self.max_concurrent)
+            .filter_map(|res| async move { res.ok() })
+            .map(|tx| self.trace_transaction(tx))
+            .buffer_unordered(self.max_concurrent)
+            .filter_map(|v| async move { v });
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,529 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use alloy::{
+    consensus::Transaction as _,
+    primitives::{Address, TxHash},
+    providers::Provider,
+    rpc::types::eth::{Block, Transaction},
+};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+
+use crate::collector::{FullBlockCollector, MempoolCollector};
+use crate::types::{Collector, CollectorStream};
+
+/// Rough per-transaction overhead of the pool indexes on top of the transaction's calldata
+const ENTRY_OVERHEAD: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub enum PoolEvent {
+    /// A new pending transaction was added to the pool
+    Added(Arc<Transaction>),
+    /// A pending transaction was replaced by one from the same sender with the same nonce and a higher fee
+    Replaced {
+        replaced: TxHash,
+        by: Arc<Transaction>,
+    },
+    /// The pool was updated for a new block
+    Block {
+        number: u64,
+        /// Pending transactions that were included in the block, or whose nonce was used by another one
+        included: usize,
+        /// Pending transactions dropped because they expired or the pool was full
+        evicted: usize,
+    },
+}
+
+struct Entry {
+    tx: Arc<Transaction>,
+    first_seen: Instant,
+    seq: u64,
+    size: usize,
+}
+
+#[derive(Default)]
+struct PoolState {
+    by_hash: HashMap<TxHash, Entry>,
+    by_sender: HashMap<Address, BTreeMap<u64, TxHash>>,
+    by_to: HashMap<Address, HashSet<TxHash>>,
+    /// Insertion order, oldest first. Entries of removed transactions are skipped lazily.
+    order: VecDeque<(u64, TxHash)>,
+    next_seq: u64,
+    size: usize,
+}
+
+impl PoolState {
+    fn insert(&mut self, tx: Transaction) -> Option<PoolEvent> {
+        let hash = *tx.inner.tx_hash();
+        if self.by_hash.contains_key(&hash) {
+            return None;
+        }
+
+        let sender = tx.inner.signer();
+        let nonce = tx.nonce();
+
+        let replaced = match self.by_sender.get(&sender).and_then(|txs| txs.get(&nonce)) {
+            Some(existing) => {
+                let existing = &self.by_hash[existing].tx;
+                let fees = (tx.max_fee_per_gas(), tx.max_priority_fee_per_gas());
+                let existing_fees = (
+                    existing.max_fee_per_gas(),
+                    existing.max_priority_fee_per_gas(),
+                );
+
+                // an underpriced replacement would have been rejected, it's most likely a stale broadcast
+                if fees <= existing_fees {
+                    return None;
+                }
+
+                let replaced = *existing.inner.tx_hash();
+                self.remove(&replaced);
+                Some(replaced)
+            }
+            None => None,
+        };
+
+        let tx = Arc::new(tx);
+        let seq = self.next_seq;
+        let size = ENTRY_OVERHEAD + tx.input().len();
+
+        self.next_seq += 1;
+        self.size += size;
+        self.order.push_back((seq, hash));
+        self.by_sender
+            .entry(sender)
+            .or_default()
+            .insert(nonce, hash);
+        if let Some(to) = tx.to() {
+            self.by_to.entry(to).or_default().insert(hash);
+        }
+        self.by_hash.insert(
+            hash,
+            Entry {
+                tx: tx.clone(),
+                first_seen: Instant::now(),
+                seq,
+                size,
+            },
+        );
+
+        Some(match replaced {
+            Some(replaced) => PoolEvent::Replaced { replaced, by: tx },
+            None => PoolEvent::Added(tx),
+        })
+    }
+
+    fn remove(&mut self, hash: &TxHash) -> bool {
+        let entry = match self.by_hash.remove(hash) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        self.size -= entry.size;
+
+        let sender = entry.tx.inner.signer();
+        if let Some(txs) = self.by_sender.get_mut(&sender) {
+            txs.remove(&entry.tx.nonce());
+            if txs.is_empty() {
+                self.by_sender.remove(&sender);
+            }
+        }
+
+        if let Some(to) = entry.tx.to() {
+            if let Some(txs) = self.by_to.get_mut(&to) {
+                txs.remove(hash);
+                if txs.is_empty() {
+                    self.by_to.remove(&to);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Remove the block's transactions, and pending transactions whose nonce was used by the block
+    fn apply_block(&mut self, block: &Block) -> usize {
+        let mut included = 0;
+
+        for tx in block.transactions.txns() {
+            let sender = tx.inner.signer();
+
+            let stale: Vec<TxHash> = match self.by_sender.get(&sender) {
+                Some(txs) => txs.range(..=tx.nonce()).map(|(_, hash)| *hash).collect(),
+                None => continue,
+            };
+
+            for hash in stale {
+                if self.remove(&hash) {
+                    included += 1;
+                }
+            }
+        }
+
+        included
+    }
+
+    /// Drop the oldest transactions until none is older than `ttl` and the pool fits in `max_size`
+    fn evict(&mut self, ttl: Duration, max_size: usize) -> usize {
+        let now = Instant::now();
+        let mut evicted = 0;
+
+        while let Some((seq, hash)) = self.order.front().copied() {
+            let entry = match self.by_hash.get(&hash) {
+                Some(entry) if entry.seq == seq => entry,
+                _ => {
+                    self.order.pop_front();
+                    continue;
+                }
+            };
+
+            if now - entry.first_seen < ttl && self.size <= max_size {
+                break;
+            }
+
+            self.order.pop_front();
+            self.remove(&hash);
+            evicted += 1;
+        }
+
+        evicted
+    }
+}
+
+/// A read handle on the pending transactions tracked by a [`PendingPoolCollector`]. Cloning it is cheap, and reads
+/// only take a short-lived lock.
+#[derive(Clone, Default)]
+pub struct PendingPool {
+    state: Arc<RwLock<PoolState>>,
+}
+
+impl PendingPool {
+    fn read(&self) -> RwLockReadGuard<'_, PoolState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, PoolState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().by_hash.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Estimated memory used by the pool, in bytes
+    pub fn size(&self) -> usize {
+        self.read().size
+    }
+
+    pub fn contains(&self, hash: &TxHash) -> bool {
+        self.read().by_hash.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &TxHash) -> Option<Arc<Transaction>> {
+        self.read().by_hash.get(hash).map(|entry| entry.tx.clone())
+    }
+
+    /// The pending transaction of `sender` with `nonce`, after replacements
+    pub fn get_by_sender_nonce(&self, sender: Address, nonce: u64) -> Option<Arc<Transaction>> {
+        let state = self.read();
+        let hash = state.by_sender.get(&sender)?.get(&nonce)?;
+        state.by_hash.get(hash).map(|entry| entry.tx.clone())
+    }
+
+    /// The pending transactions of `sender`, ordered by nonce
+    pub fn get_by_sender(&self, sender: Address) -> Vec<Arc<Transaction>> {
+        let state = self.read();
+        let hashes = match state.by_sender.get(&sender) {
+            Some(v) => v,
+            None => return vec![],
+        };
+
+        hashes
+            .values()
+            .filter_map(|hash| state.by_hash.get(hash))
+            .map(|entry| entry.tx.clone())
+            .collect()
+    }
+
+    /// The pending transactions sent to `to`
+    pub fn get_by_to(&self, to: Address) -> Vec<Arc<Transaction>> {
+        let state = self.read();
+        let hashes = match state.by_to.get(&to) {
+            Some(v) => v,
+            None => return vec![],
+        };
+
+        hashes
+            .iter()
+            .filter_map(|hash| state.by_hash.get(hash))
+            .map(|entry| entry.tx.clone())
+            .collect()
+    }
+}
+
+enum Input {
+    Pending(Box<Transaction>),
+    Block(Box<Block>),
+}
+
+/// Maintains a [`PendingPool`] from `MempoolCollector` and `FullBlockCollector`, and yields what changed in it.
+/// Strategies query the pool through the handle returned by [`PendingPoolCollector::pool`].
+pub struct PendingPoolCollector {
+    mempool: MempoolCollector,
+    blocks: FullBlockCollector,
+    pool: PendingPool,
+    ttl: Duration,
+    max_size: usize,
+}
+
+impl PendingPoolCollector {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self::new_with_collectors(
+            MempoolCollector::new(provider.clone()),
+            FullBlockCollector::new(provider),
+        )
+    }
+
+    pub fn new_with_collectors(mempool: MempoolCollector, blocks: FullBlockCollector) -> Self {
+        Self {
+            mempool,
+            blocks,
+            pool: PendingPool::default(),
+            ttl: Duration::from_secs(600),
+            max_size: 256 * 1024 * 1024,
+        }
+    }
+
+    /// Evict pending transactions that were not included within `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Evict the oldest pending transactions once the pool's estimated size exceeds `max_size` bytes
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn pool(&self) -> PendingPool {
+        self.pool.clone()
+    }
+}
+
+#[async_trait]
+impl Collector<PoolEvent> for PendingPoolCollector {
+    fn name(&self) -> &str {
+        "PendingPoolCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, PoolEvent>> {
+        let pending = self.mempool.get_event_stream().await?;
+        let blocks = self.blocks.get_event_stream().await?;
+
+        let mut inputs = stream::select(
+            pending.map(|tx| Input::Pending(Box::new(tx))),
+            blocks.map(|block| Input::Block(Box::new(block))),
+        );
+
+        let stream = async_stream::stream! {
+            while let Some(input) = inputs.next().await {
+                let event = match input {
+                    Input::Pending(tx) => {
+                        let mut state = self.pool.write();
+                        let event = state.insert(*tx);
+                        state.evict(self.ttl, self.max_size);
+                        event
+                    }
+                    Input::Block(block) => {
+                        let mut state = self.pool.write();
+                        let included = state.apply_block(&block);
+                        let evicted = state.evict(self.ttl, self.max_size);
+
+                        Some(PoolEvent::Block { number: block.header.number, included, evicted })
+                    }
+                };
+
+                if let Some(event) = event {
+                    yield event;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{transaction::Recovered, Signed, TxEip1559, TxEnvelope},
+        primitives::{Bytes, Signature, TxKind, U256},
+        rpc::types::eth::BlockTransactions,
+    };
+
+    use super::*;
+
+    const ALICE: Address = Address::repeat_byte(1);
+    const BOB: Address = Address::repeat_byte(2);
+    const POOL: Address = Address::repeat_byte(9);
+
+    fn tx(sender: Address, nonce: u64, max_fee: u128) -> Transaction {
+        let tx = TxEip1559 {
+            nonce,
+            max_fee_per_gas: max_fee,
+            max_priority_fee_per_gas: max_fee / 10,
+            to: TxKind::Call(POOL),
+            // the fake signature doesn't depend on the sender, so make the hash depend on it
+            input: Bytes::from(vec![sender.0[0]; 100]),
+            ..Default::default()
+        };
+        let signature = Signature::new(U256::from(1), U256::from(1), false);
+        let envelope = TxEnvelope::Eip1559(Signed::new_unhashed(tx, signature));
+
+        Transaction {
+            inner: Recovered::new_unchecked(envelope, sender),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+        }
+    }
+
+    fn hash(tx: &Transaction) -> TxHash {
+        *tx.inner.tx_hash()
+    }
+
+    fn block(txs: Vec<Transaction>) -> Block {
+        Block {
+            transactions: BlockTransactions::Full(txs),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adds_and_indexes_transactions() {
+        let mut pool = PoolState::default();
+        let a = tx(ALICE, 0, 100);
+
+        assert!(matches!(pool.insert(a.clone()), Some(PoolEvent::Added(_))));
+        assert!(pool.insert(a.clone()).is_none());
+
+        assert_eq!(pool.by_hash.len(), 1);
+        assert_eq!(pool.by_sender[&ALICE][&0], hash(&a));
+        assert!(pool.by_to[&POOL].contains(&hash(&a)));
+        assert_eq!(pool.size, ENTRY_OVERHEAD + 100);
+    }
+
+    #[test]
+    fn replaces_transactions_paying_more_at_the_same_nonce() {
+        let mut pool = PoolState::default();
+        let original = tx(ALICE, 0, 100);
+        let replacement = tx(ALICE, 0, 120);
+
+        pool.insert(original.clone());
+
+        match pool.insert(replacement.clone()) {
+            Some(PoolEvent::Replaced { replaced, by }) => {
+                assert_eq!(replaced, hash(&original));
+                assert_eq!(hash(&by), hash(&replacement));
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+
+        assert!(!pool.by_hash.contains_key(&hash(&original)));
+        assert_eq!(pool.by_sender[&ALICE][&0], hash(&replacement));
+        assert_eq!(pool.by_to[&POOL].len(), 1);
+        assert_eq!(pool.size, ENTRY_OVERHEAD + 100);
+    }
+
+    #[test]
+    fn ignores_underpriced_replacements() {
+        let mut pool = PoolState::default();
+        let original = tx(ALICE, 0, 100);
+
+        pool.insert(original.clone());
+
+        assert!(pool.insert(tx(ALICE, 0, 90)).is_none());
+        assert_eq!(pool.by_sender[&ALICE][&0], hash(&original));
+    }
+
+    #[test]
+    fn removes_included_transactions_and_used_nonces() {
+        let mut pool = PoolState::default();
+        for nonce in 0..3 {
+            pool.insert(tx(ALICE, nonce, 100));
+        }
+        pool.insert(tx(BOB, 0, 100));
+
+        // nonce 1 of alice was included with another tx, so her pending nonce 0 is gone too
+        let included = pool.apply_block(&block(vec![tx(ALICE, 1, 500)]));
+
+        assert_eq!(included, 2);
+        assert_eq!(
+            pool.by_sender[&ALICE].keys().copied().collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(pool.by_sender[&BOB].len(), 1);
+        assert_eq!(pool.by_hash.len(), 2);
+    }
+
+    #[test]
+    fn cleans_up_empty_indexes() {
+        let mut pool = PoolState::default();
+        let a = tx(ALICE, 0, 100);
+        pool.insert(a.clone());
+
+        assert!(pool.remove(&hash(&a)));
+        assert!(!pool.remove(&hash(&a)));
+
+        assert!(pool.by_sender.is_empty());
+        assert!(pool.by_to.is_empty());
+        assert_eq!(pool.size, 0);
+    }
+
+    #[test]
+    fn evicts_oldest_transactions_beyond_max_size() {
+        let mut pool = PoolState::default();
+        let txs: Vec<_> = (0..4).map(|nonce| tx(ALICE, nonce, 100)).collect();
+        for tx in &txs {
+            pool.insert(tx.clone());
+        }
+
+        let evicted = pool.evict(Duration::from_secs(600), 2 * (ENTRY_OVERHEAD + 100));
+
+        assert_eq!(evicted, 2);
+        assert!(!pool.by_hash.contains_key(&hash(&txs[0])));
+        assert!(!pool.by_hash.contains_key(&hash(&txs[1])));
+        assert!(pool.by_hash.contains_key(&hash(&txs[2])));
+    }
+
+    #[test]
+    fn evicts_expired_transactions() {
+        let mut pool = PoolState::default();
+        pool.insert(tx(ALICE, 0, 100));
+        pool.insert(tx(BOB, 0, 100));
+
+        assert_eq!(pool.evict(Duration::ZERO, usize::MAX), 2);
+        assert!(pool.by_hash.is_empty());
+        assert!(pool.order.is_empty());
+    }
+
+    #[test]
+    fn skips_removed_transactions_when_evicting() {
+        let mut pool = PoolState::default();
+        pool.insert(tx(ALICE, 0, 100));
+        pool.insert(tx(BOB, 0, 100));
+        pool.insert(tx(ALICE, 0, 200));
+
+        // the replaced transaction is still in the insertion order, but isn't counted
+        assert_eq!(pool.evict(Duration::ZERO, usize::MAX), 2);
+        assert!(pool.order.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::error;
+
+use crate::collector::MissedTickPolicy;
+use crate::types::{Collector, CollectorStream};
+
+/// When [`ScheduleCollector`] fires, in wall-clock time.
+#[derive(Debug, Clone)]
+pub enum TickSchedule {
+    /// A cron expression with a leading seconds field, e.g. `0 * * * * *` for every minute at :00
+    Cron(Box<cron::Schedule>),
+    /// Every `period` since the UNIX epoch shifted by `offset`, e.g. a period of one day fires daily at 00:00 UTC
+    Aligned { period: Duration, offset: Duration },
+}
+
+impl TickSchedule {
+    /// The first time on the schedule strictly after `after`
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Cron(schedule) => schedule.after(&after).next(),
+            Self::Aligned { period, offset } => {
+                let period = i64::try_from(period.as_millis()).ok().filter(|v| *v > 0)?;
+                let offset = i64::try_from(offset.as_millis()).ok()?;
+
+                let elapsed = after.timestamp_millis() - offset;
+                let next = (elapsed.div_euclid(period) + 1) * period + offset;
+
+                DateTime::from_timestamp_millis(next)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub scheduled_at: DateTime<Utc>,
+    pub fired_at: DateTime<Utc>,
+}
+
+/// Fires on a wall-clock schedule instead of a fixed sleep, so ticks don't drift.
+pub struct ScheduleCollector {
+    schedule: TickSchedule,
+    missed_tick_policy: MissedTickPolicy,
+}
+
+impl ScheduleCollector {
+    pub fn new(schedule: TickSchedule) -> Self {
+        Self {
+            schedule,
+            missed_tick_policy: MissedTickPolicy::default(),
+        }
+    }
+
+    pub fn new_cron(expression: &str) -> eyre::Result<Self> {
+        let schedule = cron::Schedule::from_str(expression)?;
+        Ok(Self::new(TickSchedule::Cron(Box::new(schedule))))
+    }
+
+    pub fn new_aligned(period: Duration) -> Self {
+        Self::new(TickSchedule::Aligned {
+            period,
+            offset: Duration::ZERO,
+        })
+    }
+
+    pub fn with_missed_tick_policy(mut self, policy: MissedTickPolicy) -> Self {
+        self.missed_tick_policy = policy;
+        self
+    }
+
+    /// The tick to fire after `scheduled_at` has fired, applying the missed tick policy if it's already overdue.
+    fn next_tick(&self, scheduled_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let next = self.schedule.next_after(scheduled_at)?;
+        let now = Utc::now();
+
+        if next > now {
+            return Some(next);
+        }
+
+        match self.missed_tick_policy {
+            MissedTickPolicy::Burst => Some(next),
+            MissedTickPolicy::Skip => self.schedule.next_after(now),
+            MissedTickPolicy::Delay => {
+                let mut latest = next;
+                while let Some(next) = self.schedule.next_after(latest).filter(|v| *v <= now) {
+                    latest = next;
+                }
+
+                Some(latest)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Collector<ScheduledTick> for ScheduleCollector {
+    fn name(&self) -> &str {
+        "ScheduleCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, ScheduledTick>> {
+        let first_tick = self.schedule.next_after(Utc::now());
+
+        let stream = async_stream::stream! {
+            let mut scheduled_at = match first_tick {
+                Some(v) => v,
+                None => {
+                    error!("schedule has no upcoming tick");
+                    return;
+                }
+            };
+
+            loop {
+                if let Ok(wait) = (scheduled_at - Utc::now()).to_std() {
+                    tokio::time::sleep(wait).await;
+                }
+
+                yield ScheduledTick {
+                    scheduled_at,
+                    fired_at: Utc::now(),
+                };
+
+                scheduled_at = match self.next_tick(scheduled_at) {
+                    Some(v) => v,
+                    None => {
+                        error!("schedule has no upcoming tick");
+                        break;
+                    }
+                };
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(millis).unwrap()
+    }
+
+    fn aligned(period_secs: u64, offset_secs: u64) -> TickSchedule {
+        TickSchedule::Aligned {
+            period: Duration::from_secs(period_secs),
+            offset: Duration::from_secs(offset_secs),
+        }
+    }
+
+    #[test]
+    fn aligned_fires_on_period_boundaries() {
+        let schedule = aligned(60, 0);
+
+        assert_eq!(schedule.next_after(at(0)), Some(at(60_000)));
+        assert_eq!(schedule.next_after(at(59_999)), Some(at(60_000)));
+        assert_eq!(schedule.next_after(at(61_500)), Some(at(120_000)));
+    }
+
+    #[test]
+    fn aligned_is_strictly_after() {
+        let schedule = aligned(60, 0);
+
+        assert_eq!(schedule.next_after(at(60_000)), Some(at(120_000)));
+    }
+
+    #[test]
+    fn aligned_applies_the_offset() {
+        let schedule = aligned(60, 15);
+
+        assert_eq!(schedule.next_after(at(0)), Some(at(15_000)));
+        assert_eq!(schedule.next_after(at(15_000)), Some(at(75_000)));
+        assert_eq!(schedule.next_after(at(80_000)), Some(at(135_000)));
+    }
+
+    #[test]
+    fn aligned_works_before_the_offset_and_the_epoch() {
+        let schedule = aligned(60, 15);
+
+        assert_eq!(schedule.next_after(at(-50_000)), Some(at(-45_000)));
+        assert_eq!(schedule.next_after(at(-45_000)), Some(at(15_000)));
+    }
+
+    #[test]
+    fn aligned_never_fires_with_a_zero_period() {
+        assert_eq!(aligned(0, 0).next_after(at(0)), None);
+        assert_eq!(
+            TickSchedule::Aligned {
+                period: Duration::from_micros(10),
+                offset: Duration::ZERO,
+            }
+            .next_after(at(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn cron_fires_on_matching_times() {
+        let schedule = TickSchedule::Cron(Box::new("0 */5 * * * *".parse().unwrap()));
+
+        assert_eq!(schedule.next_after(at(0)), Some(at(300_000)));
+        assert_eq!(schedule.next_after(at(300_000)), Some(at(600_000)));
+        assert_eq!(schedule.next_after(at(301_000)), Some(at(600_000)));
+    }
+
+    #[test]
+    fn rejects_invalid_cron_expressions() {
+        assert!(ScheduleCollector::new_cron("every minute").is_err());
+        assert!(ScheduleCollector::new_cron("0 * * * * *").is_ok());
+    }
+}
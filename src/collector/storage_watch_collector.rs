@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::{client::BatchRequest, types::eth::BlockId},
+    transports::TransportResult,
+};
+use async_trait::async_trait;
+use futures::{future::try_join_all, StreamExt};
+use tracing::error;
+
+use crate::types::{Collector, CollectorStream};
+
+/// A single piece of on-chain state watched by [`StorageWatchCollector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Watch {
+    /// Storage `slot` of the contract at `address`
+    Storage(Address, U256),
+    /// Ether balance of `address`
+    Balance(Address),
+}
+
+/// Watched values that changed in `block_number`.
+#[derive(Debug, Clone)]
+pub struct WatchChanges {
+    pub block_number: u64,
+    pub changes: Vec<(Watch, U256)>,
+}
+
+/// Reads a set of storage slots and balances on every new block with one batched JSON-RPC request and emits the
+/// ones that changed. The first block reports every watched value.
+pub struct StorageWatchCollector {
+    provider: Arc<dyn Provider>,
+    watches: Vec<Watch>,
+}
+
+impl StorageWatchCollector {
+    pub fn new(provider: Arc<dyn Provider>, watches: Vec<Watch>) -> Self {
+        Self { provider, watches }
+    }
+
+    pub fn watch_storage(mut self, address: Address, slot: U256) -> Self {
+        self.watches.push(Watch::Storage(address, slot));
+        self
+    }
+
+    pub fn watch_balance(mut self, address: Address) -> Self {
+        self.watches.push(Watch::Balance(address));
+        self
+    }
+
+    async fn read_watches(&self, block_number: u64) -> TransportResult<Vec<U256>> {
+        // nodes reject empty batches
+        if self.watches.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let block = BlockId::number(block_number);
+        let mut batch = BatchRequest::new(self.provider.client());
+
+        let waiters = self
+            .watches
+            .iter()
+            .map(|watch| match watch {
+                Watch::Storage(address, slot) => {
+                    batch.add_call("eth_getStorageAt", &(address, slot, block))
+                }
+                Watch::Balance(address) => batch.add_call("eth_getBalance", &(address, block)),
+            })
+            .collect::<TransportResult<Vec<_>>>()?;
+
+        batch.send().await?;
+
+        try_join_all(waiters).await
+    }
+
+    /// The watches whose value in `values` differs from `last_values`, which is updated
+    fn changes(
+        &self,
+        last_values: &mut HashMap<Watch, U256>,
+        values: Vec<U256>,
+    ) -> Vec<(Watch, U256)> {
+        self.watches
+            .iter()
+            .zip(values)
+            .filter(|(watch, value)| last_values.insert(**watch, *value) != Some(*value))
+            .map(|(watch, value)| (*watch, value))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Collector<WatchChanges> for StorageWatchCollector {
+    fn name(&self) -> &str {
+        "StorageWatchCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, WatchChanges>> {
+        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
+
+        let stream = async_stream::stream! {
+            let mut last_values = HashMap::<Watch, U256>::new();
+
+            while let Some(block) = stream.next().await {
+                let values = match self.read_watches(block.number).await {
+                    Ok(values) => values,
+                    Err(e) => {
+                        error!(block = block.number, "fail to read watched state: {e:#}");
+                        continue;
+                    }
+                };
+
+                let changes = self.changes(&mut last_values, values);
+
+                if changes.is_empty() {
+                    continue;
+                }
+
+                yield WatchChanges {
+                    block_number: block.number,
+                    changes,
+                };
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::providers::{mock::Asserter, ProviderBuilder};
+
+    use super::*;
+
+    const TOKEN: Address = Address::repeat_byte(1);
+    const HOLDER: Address = Address::repeat_byte(2);
+
+    fn collector(watches: Vec<Watch>) -> (StorageWatchCollector, Asserter) {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        (
+            StorageWatchCollector::new(Arc::new(provider), watches),
+            asserter,
+        )
+    }
+
+    fn values(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|v| U256::from(*v)).collect()
+    }
+
+    #[test]
+    fn reports_every_value_first_then_only_changes() {
+        let slot = Watch::Storage(TOKEN, U256::from(3));
+        let balance = Watch::Balance(HOLDER);
+        let (collector, _) = collector(vec![slot, balance]);
+        let mut last_values = HashMap::new();
+
+        assert_eq!(
+            collector.changes(&mut last_values, values(&[1, 0])),
+            vec![(slot, U256::from(1)), (balance, U256::ZERO)]
+        );
+        assert!(collector
+            .changes(&mut last_values, values(&[1, 0]))
+            .is_empty());
+        assert_eq!(
+            collector.changes(&mut last_values, values(&[1, 5])),
+            vec![(balance, U256::from(5))]
+        );
+        assert_eq!(
+            collector.changes(&mut last_values, values(&[2, 5])),
+            vec![(slot, U256::from(2))]
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_watches_in_one_batch() {
+        let (collector, asserter) = collector(vec![]);
+        let collector = collector
+            .watch_storage(TOKEN, U256::from(3))
+            .watch_balance(HOLDER);
+
+        asserter.push_success(&U256::from(7));
+        asserter.push_success(&U256::from(9));
+
+        assert_eq!(collector.read_watches(100).await.unwrap(), values(&[7, 9]));
+    }
+
+    #[tokio::test]
+    async fn sends_nothing_without_watches() {
+        let (collector, asserter) = collector(vec![]);
+        asserter.push_failure_msg("unexpected request");
+
+        assert!(collector.read_watches(100).await.unwrap().is_empty());
+        assert_eq!(asserter.read_q().len(), 1);
+    }
+}
@@ -0,0 +1,418 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::{error, warn};
+
+use crate::types::{Collector, CollectorStream};
+
+/// Where a [`TelegramCommand`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandSource {
+    Message {
+        message_id: i64,
+    },
+    /// A press on an inline keyboard button, `data` of the button is parsed as the command
+    CallbackQuery {
+        id: String,
+    },
+}
+
+/// A bot command such as `/set threshold 5`, parsed into `command = "set"` and `args = ["threshold", "5"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelegramCommand {
+    pub update_id: i64,
+    pub chat_id: i64,
+    pub user_id: i64,
+    pub source: CommandSource,
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl TelegramCommand {
+    fn parse(update: &Value) -> Option<Self> {
+        let update_id = update["update_id"].as_i64()?;
+
+        let (chat_id, user_id, source, text) = if let Some(message) = update.get("message") {
+            let text = message["text"].as_str()?;
+            if !text.starts_with('/') {
+                return None;
+            }
+
+            (
+                message["chat"]["id"].as_i64()?,
+                message["from"]["id"].as_i64()?,
+                CommandSource::Message {
+                    message_id: message["message_id"].as_i64()?,
+                },
+                text,
+            )
+        } else if let Some(query) = update.get("callback_query") {
+            (
+                query["message"]["chat"]["id"].as_i64()?,
+                query["from"]["id"].as_i64()?,
+                CommandSource::CallbackQuery {
+                    id: query["id"].as_str()?.to_string(),
+                },
+                query["data"].as_str()?,
+            )
+        } else {
+            return None;
+        };
+
+        let mut parts = text.split_whitespace();
+
+        // drop the leading slash and the `@bot_name` suffix used in group chats
+        let command = parts.next()?.trim_start_matches('/');
+        let command = command.split('@').next()?.to_string();
+
+        Some(Self {
+            update_id,
+            chat_id,
+            user_id,
+            source,
+            command,
+            args: parts.map(|v| v.to_string()).collect(),
+        })
+    }
+}
+
+/// Long polls `getUpdates` and yields the bot commands sent by allowed chats and users.
+pub struct TelegramCommandCollector {
+    bot_token: String,
+    api_url: String,
+    allowed_chat_ids: HashSet<i64>,
+    allowed_user_ids: HashSet<i64>,
+    allow_anyone: bool,
+    poll_timeout: Duration,
+    retry_interval: Duration,
+
+    client: reqwest::Client,
+}
+
+impl TelegramCommandCollector {
+    pub fn new<T: Into<String>>(bot_token: T) -> Self {
+        Self {
+            bot_token: bot_token.into(),
+            api_url: "https://api.telegram.org".to_string(),
+            allowed_chat_ids: HashSet::new(),
+            allowed_user_ids: HashSet::new(),
+            allow_anyone: false,
+            poll_timeout: Duration::from_secs(30),
+            retry_interval: Duration::from_secs(1),
+            client: reqwest::ClientBuilder::new().build().unwrap(),
+        }
+    }
+
+    /// Use another Bot API server, e.g. a self-hosted one or a mock in tests
+    pub fn with_api_url<T: Into<String>>(mut self, api_url: T) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// Only accept commands from this chat. Commands from any chat are accepted if only users are allowed.
+    pub fn allow_chat(mut self, chat_id: i64) -> Self {
+        self.allowed_chat_ids.insert(chat_id);
+        self
+    }
+
+    /// Only accept commands from this user. Commands from any user are accepted if only chats are allowed.
+    pub fn allow_user(mut self, user_id: i64) -> Self {
+        self.allowed_user_ids.insert(user_id);
+        self
+    }
+
+    /// Accept commands from any chat and user when no allow-list is set. Without it, the collector refuses to start
+    /// without an allow-list, since anyone who finds the bot could control it.
+    pub fn allow_anyone(mut self) -> Self {
+        self.allow_anyone = true;
+        self
+    }
+
+    pub fn with_poll_timeout(mut self, poll_timeout: Duration) -> Self {
+        self.poll_timeout = poll_timeout;
+        self
+    }
+
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    fn get_url(&self, method: &str) -> String {
+        format!("{}/bot{}/{method}", self.api_url, self.bot_token)
+    }
+
+    fn is_allowed(&self, command: &TelegramCommand) -> bool {
+        (self.allowed_chat_ids.is_empty() || self.allowed_chat_ids.contains(&command.chat_id))
+            && (self.allowed_user_ids.is_empty()
+                || self.allowed_user_ids.contains(&command.user_id))
+    }
+
+    async fn get_updates(&self, offset: i64) -> eyre::Result<Vec<Value>> {
+        let data = json!({
+            "offset": offset,
+            "timeout": self.poll_timeout.as_secs(),
+            "allowed_updates": ["message", "callback_query"],
+        });
+
+        let response = self
+            .client
+            .post(self.get_url("getUpdates"))
+            .timeout(self.poll_timeout + Duration::from_secs(10))
+            .json(&data)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        if response["ok"].as_bool() != Some(true) {
+            eyre::bail!("getUpdates failed: {response}");
+        }
+
+        match response["result"].as_array() {
+            Some(updates) => Ok(updates.clone()),
+            None => eyre::bail!("unexpected getUpdates response: {response}"),
+        }
+    }
+
+    /// Stops the loading indicator on the button that triggered the callback query.
+    async fn answer_callback_query(&self, id: &str) {
+        let response = self
+            .client
+            .post(self.get_url("answerCallbackQuery"))
+            .json(&json!({ "callback_query_id": id }))
+            .send()
+            .await;
+
+        if let Err(err) = response {
+            warn!("fail to answer callback query: {err:#}");
+        }
+    }
+}
+
+#[async_trait]
+impl Collector<TelegramCommand> for TelegramCommandCollector {
+    fn name(&self) -> &str {
+        "TelegramCommandCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, TelegramCommand>> {
+        if self.allowed_chat_ids.is_empty() && self.allowed_user_ids.is_empty() {
+            eyre::ensure!(
+                self.allow_anyone,
+                "no chat or user allow-list is set, use `allow_anyone` to accept commands from anyone"
+            );
+            warn!("no chat or user allow-list is set, accepting commands from anyone");
+        }
+
+        let stream = async_stream::stream! {
+            let mut offset = 0;
+
+            loop {
+                let updates = match self.get_updates(offset).await {
+                    Ok(updates) => updates,
+                    Err(err) => {
+                        error!("fail to get telegram updates: {err:#}");
+                        tokio::time::sleep(self.retry_interval).await;
+                        continue;
+                    }
+                };
+
+                for update in updates {
+                    if let Some(update_id) = update["update_id"].as_i64() {
+                        offset = offset.max(update_id + 1);
+                    }
+
+                    let command = match TelegramCommand::parse(&update) {
+                        Some(command) => command,
+                        None => continue,
+                    };
+
+                    if !self.is_allowed(&command) {
+                        warn!(
+                            chat_id = command.chat_id,
+                            user_id = command.user_id,
+                            "ignored command from chat or user not in allow-list"
+                        );
+                        continue;
+                    }
+
+                    if let CommandSource::CallbackQuery { id } = &command.source {
+                        self.answer_callback_query(id).await;
+                    }
+
+                    yield command;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves one batch of `updates` to the first `getUpdates`, then empty batches, and records the called methods
+    async fn mock_bot_api(updates: Value) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let calls = Arc::new(Mutex::new(vec![]));
+
+        let recorded = calls.clone();
+        tokio::spawn(async move {
+            let mut updates = Some(updates);
+
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+
+                let mut request = vec![];
+                let mut buf = [0; 4096];
+                let (header_end, content_length) = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let content_length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse().unwrap())
+                            })
+                            .unwrap_or(0);
+                        break (end + 4, content_length);
+                    }
+                };
+                while request.len() < header_end + content_length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+
+                let text = String::from_utf8_lossy(&request);
+                let path = text.split_whitespace().nth(1).unwrap();
+                let method = path.rsplit('/').next().unwrap().to_string();
+                recorded.lock().unwrap().push(method.clone());
+
+                let body = match method.as_str() {
+                    "getUpdates" => match updates.take() {
+                        Some(updates) => json!({ "ok": true, "result": updates }),
+                        None => {
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            json!({ "ok": true, "result": [] })
+                        }
+                    },
+                    _ => json!({ "ok": true, "result": true }),
+                }
+                .to_string();
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, calls)
+    }
+
+    fn message(update_id: i64, user_id: i64, text: &str) -> Value {
+        json!({
+            "update_id": update_id,
+            "message": {
+                "message_id": update_id,
+                "chat": { "id": 1 },
+                "from": { "id": user_id },
+                "text": text,
+            },
+        })
+    }
+
+    fn callback_query(update_id: i64, user_id: i64, data: &str) -> Value {
+        json!({
+            "update_id": update_id,
+            "callback_query": {
+                "id": format!("query-{update_id}"),
+                "message": { "chat": { "id": 1 } },
+                "from": { "id": user_id },
+                "data": data,
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn yields_commands_of_allowed_users_only() {
+        let updates = json!([
+            message(1, 666, "/pause"),
+            callback_query(2, 666, "/set threshold 1"),
+            message(3, 42, "hello"),
+            message(4, 42, "/set@my_bot threshold 5"),
+            callback_query(5, 42, "/resume"),
+        ]);
+        let (url, calls) = mock_bot_api(updates).await;
+
+        let collector = TelegramCommandCollector::new("token")
+            .with_api_url(url)
+            .allow_user(42)
+            .with_poll_timeout(Duration::ZERO);
+        let stream = collector.get_event_stream().await.unwrap();
+
+        let commands: Vec<_> =
+            tokio::time::timeout(Duration::from_secs(5), stream.take(2).collect())
+                .await
+                .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                TelegramCommand {
+                    update_id: 4,
+                    chat_id: 1,
+                    user_id: 42,
+                    source: CommandSource::Message { message_id: 4 },
+                    command: "set".to_string(),
+                    args: vec!["threshold".to_string(), "5".to_string()],
+                },
+                TelegramCommand {
+                    update_id: 5,
+                    chat_id: 1,
+                    user_id: 42,
+                    source: CommandSource::CallbackQuery {
+                        id: "query-5".to_string()
+                    },
+                    command: "resume".to_string(),
+                    args: vec![],
+                },
+            ]
+        );
+
+        // only the allowed callback query is answered
+        let answered = calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|method| *method == "answerCallbackQuery")
+            .count();
+        assert_eq!(answered, 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_to_start_without_allow_list() {
+        let collector = TelegramCommandCollector::new("token");
+        assert!(collector.get_event_stream().await.is_err());
+
+        let collector = TelegramCommandCollector::new("token").allow_anyone();
+        assert!(collector.get_event_stream().await.is_ok());
+    }
+}
@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use eyre::WrapErr;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, warn};
+
+use crate::types::{Collector, CollectorStream};
+
+/// How [`WebhookCollector`] authenticates incoming requests.
+#[derive(Debug, Clone, Default)]
+pub enum WebhookAuth {
+    #[default]
+    None,
+    /// `header` must carry `secret` verbatim
+    SharedSecret { header: String, secret: String },
+    /// `header` must carry the hex encoded HMAC-SHA256 of the request body, optionally prefixed with `sha256=`
+    HmacSha256 { header: String, secret: Vec<u8> },
+}
+
+impl WebhookAuth {
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> bool {
+        let (header, expected) = match self {
+            Self::None => return true,
+            Self::SharedSecret { header, secret } => (header, secret.clone()),
+            Self::HmacSha256 { header, secret } => {
+                let mut mac = match Hmac::<Sha256>::new_from_slice(secret) {
+                    Ok(v) => v,
+                    Err(_) => return false,
+                };
+                mac.update(body);
+
+                let digest = mac.finalize().into_bytes();
+                (header, digest.iter().map(|b| format!("{b:02x}")).collect())
+            }
+        };
+
+        let provided = match headers.get(header).and_then(|v| v.to_str().ok()) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        let provided = match self {
+            Self::HmacSha256 { .. } => provided.trim_start_matches("sha256=").to_lowercase(),
+            _ => provided.to_string(),
+        };
+
+        constant_time_eq(provided.as_bytes(), expected.as_bytes())
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+type Delivery<E> = (E, oneshot::Sender<()>);
+
+struct WebhookState<E> {
+    auth: WebhookAuth,
+    sender: mpsc::Sender<Delivery<E>>,
+}
+
+/// Runs an embedded HTTP server and turns the JSON body of every `POST` to `path` into an event. The request is only
+/// answered with `200 OK` once the event has been handed to the engine. The server stops when the event stream is
+/// dropped.
+pub struct WebhookCollector<E> {
+    address: SocketAddr,
+    path: String,
+    auth: WebhookAuth,
+    _phantom: PhantomData<fn() -> E>,
+}
+
+impl<E> WebhookCollector<E> {
+    pub fn new<T: Into<String>>(address: SocketAddr, path: T) -> Self {
+        Self {
+            address,
+            path: path.into(),
+            auth: WebhookAuth::None,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_auth(mut self, auth: WebhookAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+}
+
+async fn handle_request<E>(
+    State(state): State<Arc<WebhookState<E>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode
+where
+    E: DeserializeOwned + Send + 'static,
+{
+    if !state.auth.verify(&headers, &body) {
+        warn!("rejected webhook request with invalid credentials");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let event = match serde_json::from_slice::<E>(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("fail to parse webhook request body: {e:#}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    let (ack_sender, ack_receiver) = oneshot::channel();
+
+    if state.sender.send((event, ack_sender)).await.is_err() {
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+
+    match ack_receiver.await {
+        Ok(_) => StatusCode::OK,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[async_trait]
+impl<E> Collector<E> for WebhookCollector<E>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "WebhookCollector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        eyre::ensure!(
+            self.path.starts_with('/'),
+            "webhook path {:?} must start with `/`",
+            self.path
+        );
+
+        let listener = tokio::net::TcpListener::bind(self.address)
+            .await
+            .wrap_err_with(|| format!("fail to bind webhook server to {}", self.address))?;
+
+        let (sender, mut receiver) = mpsc::channel::<Delivery<E>>(64);
+
+        let state = Arc::new(WebhookState {
+            auth: self.auth.clone(),
+            sender,
+        });
+
+        // stop the server once the stream is dropped
+        let closed = state.sender.clone();
+
+        let app = Router::new()
+            .route(&self.path, post(handle_request::<E>))
+            .with_state(state);
+
+        tokio::spawn(async move {
+            let server = axum::serve(listener, app)
+                .with_graceful_shutdown(async move { closed.closed().await });
+
+            if let Err(e) = server.await {
+                error!("webhook server stopped: {e:#}");
+            }
+        });
+
+        let stream = async_stream::stream! {
+            while let Some((event, ack)) = receiver.recv().await {
+                yield event;
+
+                // the stream is polled again only after the engine has broadcast the event
+                let _ = ack.send(());
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    const BODY: &[u8] = br#"{"id":1}"#;
+
+    fn headers(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            value.parse().unwrap(),
+        );
+        headers
+    }
+
+    fn hmac_hex(secret: &[u8], body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(body);
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// An address with a port that was free a moment ago
+    fn free_address() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    /// POST `body` to `path`, and return the status code of the response
+    async fn post(address: SocketAddr, path: &str, body: &[u8]) -> u16 {
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        let request = format!(
+            "POST {path} HTTP/1.1\r\nhost: {address}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            body.len()
+        );
+        socket.write_all(request.as_bytes()).await.unwrap();
+        socket.write_all(body).await.unwrap();
+
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        response.split_whitespace().nth(1).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn verifies_shared_secrets() {
+        let auth = WebhookAuth::SharedSecret {
+            header: "x-token".to_string(),
+            secret: "hunter2".to_string(),
+        };
+
+        assert!(auth.verify(&headers("x-token", "hunter2"), BODY));
+        assert!(!auth.verify(&headers("x-token", "hunter3"), BODY));
+        assert!(!auth.verify(&headers("x-token", "hunter22"), BODY));
+        assert!(!auth.verify(&headers("x-other", "hunter2"), BODY));
+        assert!(!auth.verify(&HeaderMap::new(), BODY));
+    }
+
+    #[test]
+    fn verifies_hmac_signatures() {
+        let auth = WebhookAuth::HmacSha256 {
+            header: "x-signature".to_string(),
+            secret: b"secret".to_vec(),
+        };
+        let signature = hmac_hex(b"secret", BODY);
+
+        assert!(auth.verify(&headers("x-signature", &signature), BODY));
+        assert!(auth.verify(
+            &headers("x-signature", &format!("sha256={signature}")),
+            BODY
+        ));
+        assert!(auth.verify(&headers("x-signature", &signature.to_uppercase()), BODY));
+
+        // signed with another secret, or over another body
+        let wrong = hmac_hex(b"other", BODY);
+        assert!(!auth.verify(&headers("x-signature", &wrong), BODY));
+        assert!(!auth.verify(&headers("x-signature", &signature), b"{}"));
+        assert!(!auth.verify(&headers("x-signature", "sha256="), BODY));
+        assert!(!auth.verify(&HeaderMap::new(), BODY));
+    }
+
+    #[test]
+    fn accepts_anything_without_auth() {
+        assert!(WebhookAuth::None.verify(&HeaderMap::new(), BODY));
+    }
+
+    #[tokio::test]
+    async fn rejects_paths_without_leading_slash() {
+        let collector = WebhookCollector::<Value>::new(free_address(), "hook");
+
+        assert!(collector.get_event_stream().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn answers_once_the_event_is_yielded() {
+        let address = free_address();
+        let collector = WebhookCollector::<Value>::new(address, "/hook");
+        let mut stream = collector.get_event_stream().await.unwrap();
+
+        let mut response = tokio::spawn(post(address, "/hook", BODY));
+
+        assert_eq!(stream.next().await, Some(json!({ "id": 1 })));
+
+        // not answered before the stream is polled again
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut response)
+                .await
+                .is_err()
+        );
+
+        tokio::select! {
+            _ = stream.next() => panic!("unexpected event"),
+            status = response => assert_eq!(status.unwrap(), 200),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthenticated_requests() {
+        let address = free_address();
+        let collector =
+            WebhookCollector::<Value>::new(address, "/hook").with_auth(WebhookAuth::SharedSecret {
+                header: "x-token".to_string(),
+                secret: "hunter2".to_string(),
+            });
+        let _stream = collector.get_event_stream().await.unwrap();
+
+        assert_eq!(post(address, "/hook", BODY).await, 401);
+    }
+
+    #[tokio::test]
+    async fn stops_the_server_once_the_stream_is_dropped() {
+        let address = free_address();
+        let collector = WebhookCollector::<Value>::new(address, "/hook");
+        let stream = collector.get_event_stream().await.unwrap();
+
+        assert!(TcpStream::connect(address).await.is_ok());
+        drop(stream);
+
+        for _ in 0..50 {
+            if TcpStream::connect(address).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("server still listening");
+    }
+}
@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use alloy::{
+    primitives::{keccak256, Bytes, TxHash},
+    providers::{Provider, ProviderBuilder},
+};
+use async_trait::async_trait;
+use futures::{stream::FuturesUnordered, StreamExt};
+
+use crate::executor::dry_run::DryRun;
+use crate::types::Executor;
+
+/// Sends the same signed transaction to several endpoints at once, e.g. the public node and a few relays.
+pub struct BroadcastSender {
+    endpoints: Vec<(String, Arc<dyn Provider>)>,
+    quorum: usize,
+    dry_run: Option<Arc<DryRun>>,
+}
+
+impl BroadcastSender {
+    /// Create a new `BroadcastSender` over named endpoints. One acceptance is enough by default.
+    pub fn new(endpoints: Vec<(String, Arc<dyn Provider>)>) -> Self {
+        assert!(!endpoints.is_empty(), "at least one endpoint is required");
+
+        Self {
+            endpoints,
+            quorum: 1,
+            dry_run: None,
+        }
+    }
+
+    pub fn new_http(urls: &[&str]) -> Self {
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                let provider = ProviderBuilder::default().connect_http(url.parse().unwrap());
+                (url.to_string(), Arc::new(provider) as Arc<dyn Provider>)
+            })
+            .collect();
+
+        Self::new(endpoints)
+    }
+
+    /// Consider a transaction sent once `quorum` endpoints accepted it
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum.clamp(1, self.endpoints.len());
+        self
+    }
+
+    /// Simulate transactions on the first endpoint and record them in `dry_run` instead of sending them
+    pub fn with_dry_run(mut self, dry_run: Arc<DryRun>) -> Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    /// Send `raw_tx` to every endpoint, and return as soon as the quorum accepted it. The other submissions carry on
+    /// in the background.
+    pub async fn broadcast(&self, raw_tx: &Bytes) -> eyre::Result<TxHash> {
+        let hash = keccak256(raw_tx);
+
+        if let Some(dry_run) = &self.dry_run {
+            dry_run
+                .record_raw(self.endpoints[0].1.as_ref(), raw_tx.clone())
+                .await;
+            return Ok(hash);
+        }
+
+        let mut submissions: FuturesUnordered<_> = self
+            .endpoints
+            .iter()
+            .map(|(name, provider)| {
+                let name = name.clone();
+                let provider = provider.clone();
+                let raw_tx = raw_tx.clone();
+
+                async move {
+                    let result = provider.send_raw_transaction(&raw_tx).await;
+                    (name, result.map(|_| ()).map_err(|err| format!("{err:#}")))
+                }
+            })
+            .collect();
+
+        let mut accepted = 0;
+        let mut rejected = vec![];
+
+        while let Some((endpoint, result)) = submissions.next().await {
+            if !report(&endpoint, hash, &result) {
+                rejected.push(format!("{endpoint}: {}", result.unwrap_err()));
+                continue;
+            }
+
+            accepted += 1;
+
+            if accepted >= self.quorum {
+                tokio::spawn(async move {
+                    while let Some((endpoint, result)) = submissions.next().await {
+                        report(&endpoint, hash, &result);
+                    }
+                });
+
+                return Ok(hash);
+            }
+        }
+
+        eyre::bail!(
+            "{accepted} of {} endpoints accepted the tx, quorum is {}: {}",
+            self.endpoints.len(),
+            self.quorum,
+            rejected.join("; ")
+        )
+    }
+}
+
+/// Log the result of a submission, and whether it counts as accepted. An endpoint that already knows the
+/// transaction got it from another one, so it counts as accepted too.
+fn report(endpoint: &str, hash: TxHash, result: &Result<(), String>) -> bool {
+    let err = match result {
+        Ok(()) => {
+            tracing::info!(endpoint, tx = ?hash, "endpoint accepted tx");
+            return true;
+        }
+        Err(err) => err,
+    };
+
+    let lowercase = err.to_lowercase();
+    if lowercase.contains("already known") || lowercase.contains("known transaction") {
+        tracing::info!(endpoint, tx = ?hash, "endpoint already knows tx");
+        return true;
+    }
+
+    tracing::warn!(endpoint, tx = ?hash, "endpoint rejected tx: {err}");
+    false
+}
+
+#[async_trait]
+impl Executor<Bytes> for BroadcastSender {
+    fn name(&self) -> &str {
+        "BroadcastSender"
+    }
+
+    async fn execute(&self, action: Bytes) -> eyre::Result<()> {
+        match self.broadcast(&action).await {
+            Ok(hash) => {
+                tracing::info!("sent tx: {:#x}", hash);
+                Ok(())
+            }
+            Err(err) => Err(eyre::eyre!(
+                "failed to send tx {:?}: {err:#}",
+                keccak256(&action)
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy::{primitives::B256, providers::mock::Asserter, rpc::json_rpc::ErrorPayload};
+
+    use super::*;
+
+    const RAW_TX: Bytes = Bytes::from_static(&[1, 2, 3]);
+
+    fn accepting() -> Arc<dyn Provider> {
+        let asserter = Asserter::new();
+        asserter.push_success(&keccak256(&RAW_TX));
+        Arc::new(ProviderBuilder::new().connect_mocked_client(asserter))
+    }
+
+    fn rejecting(message: &'static str) -> Arc<dyn Provider> {
+        let asserter = Asserter::new();
+        asserter.push_failure(ErrorPayload {
+            code: -32000,
+            message: message.into(),
+            data: None,
+        });
+        Arc::new(ProviderBuilder::new().connect_mocked_client(asserter))
+    }
+
+    fn sender(endpoints: Vec<Arc<dyn Provider>>) -> BroadcastSender {
+        BroadcastSender::new(
+            endpoints
+                .into_iter()
+                .enumerate()
+                .map(|(i, provider)| (format!("endpoint-{i}"), provider))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn counts_known_transactions_as_accepted() {
+        let hash = B256::ZERO;
+
+        assert!(report("a", hash, &Ok(())));
+        assert!(report("a", hash, &Err("already known".to_string())));
+        assert!(report(
+            "a",
+            hash,
+            &Err("Known transaction: 0x1234".to_string())
+        ));
+        assert!(!report("a", hash, &Err("nonce too low".to_string())));
+        assert!(!report(
+            "a",
+            hash,
+            &Err("replacement transaction underpriced".to_string())
+        ));
+    }
+
+    #[tokio::test]
+    async fn succeeds_once_the_quorum_accepted() {
+        let sender = sender(vec![
+            accepting(),
+            rejecting("nonce too low"),
+            rejecting("already known"),
+        ])
+        .with_quorum(2);
+
+        assert_eq!(sender.broadcast(&RAW_TX).await.unwrap(), keccak256(&RAW_TX));
+    }
+
+    #[tokio::test]
+    async fn fails_below_the_quorum() {
+        let sender = sender(vec![
+            accepting(),
+            rejecting("nonce too low"),
+            rejecting("fee too low"),
+        ])
+        .with_quorum(2);
+
+        let err = sender.broadcast(&RAW_TX).await.unwrap_err().to_string();
+        assert!(
+            err.starts_with("1 of 3 endpoints accepted the tx, quorum is 2"),
+            "{err}"
+        );
+        assert!(
+            err.contains("nonce too low") && err.contains("fee too low"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_when_every_endpoint_rejects() {
+        let sender = sender(vec![rejecting("nonce too low"), rejecting("nonce too low")]);
+
+        assert!(sender.broadcast(&RAW_TX).await.is_err());
+        assert!(sender.execute(RAW_TX).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn returns_at_quorum_without_waiting_for_slow_endpoints() {
+        // accepts connections but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let slow = Arc::new(ProviderBuilder::new().connect_http(url.parse().unwrap()));
+        let sender = sender(vec![slow, accepting()]);
+
+        let hash = tokio::time::timeout(Duration::from_secs(5), sender.broadcast(&RAW_TX)).await;
+        assert_eq!(hash.unwrap().unwrap(), keccak256(&RAW_TX));
+    }
+}
@@ -0,0 +1,297 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::{
+    hex,
+    primitives::{keccak256, Bytes, TxHash},
+    providers::Provider,
+    signers::{local::PrivateKeySigner, SignerSync},
+};
+use async_trait::async_trait;
+use eyre::WrapErr;
+use serde_json::{json, Value};
+
+use crate::executor::dry_run::DryRun;
+use crate::types::Executor;
+
+/// A bundle of signed transactions to be included in `target_block`, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bundle {
+    pub txs: Vec<Bytes>,
+    pub target_block: u64,
+    pub min_timestamp: Option<u64>,
+    pub max_timestamp: Option<u64>,
+    /// Transactions of the bundle that are allowed to revert
+    pub reverting_hashes: Vec<TxHash>,
+}
+
+impl Bundle {
+    fn params(&self) -> Value {
+        let mut params = json!({
+            "txs": self.txs,
+            "blockNumber": format!("{:#x}", self.target_block),
+        });
+
+        if let Some(min_timestamp) = self.min_timestamp {
+            params["minTimestamp"] = json!(min_timestamp);
+        }
+
+        if let Some(max_timestamp) = self.max_timestamp {
+            params["maxTimestamp"] = json!(max_timestamp);
+        }
+
+        if !self.reverting_hashes.is_empty() {
+            params["revertingTxHashes"] = json!(self.reverting_hashes);
+        }
+
+        params
+    }
+}
+
+/// Signs and sends JSON-RPC requests to builders
+struct Relay {
+    client: reqwest::Client,
+    reputation_key: PrivateKeySigner,
+}
+
+impl Relay {
+    async fn request(&self, endpoint: &str, method: &str, params: Value) -> eyre::Result<Value> {
+        let body = serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": [params],
+        }))?;
+
+        // the header is the signature of the hex encoded body hash, as an Ethereum message
+        let hash = hex::encode_prefixed(keccak256(&body));
+        let signature = self.reputation_key.sign_message_sync(hash.as_bytes())?;
+        let header = format!(
+            "{:?}:{}",
+            self.reputation_key.address(),
+            hex::encode_prefixed(signature.as_bytes())
+        );
+
+        let response: Value = self
+            .client
+            .post(endpoint)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", header)
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(err) = response.get("error") {
+            eyre::bail!("{method} failed: {err}");
+        }
+
+        Ok(response.get("result").cloned().unwrap_or_default())
+    }
+
+    async fn send_bundle(&self, builders: &[String], bundle: &Bundle) {
+        let params = bundle.params();
+
+        let results = futures::future::join_all(
+            builders
+                .iter()
+                .map(|builder| self.request(builder, "eth_sendBundle", params.clone())),
+        )
+        .await;
+
+        for (builder, result) in builders.iter().zip(results) {
+            match result {
+                Ok(result) => {
+                    tracing::info!(builder, block = bundle.target_block, %result, "sent bundle")
+                }
+                Err(err) => {
+                    tracing::error!(
+                        builder,
+                        block = bundle.target_block,
+                        "failed to send bundle: {err:#}"
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Sends [`Bundle`]s to one or more block builders with `eth_sendBundle`, signing requests with a reputation key.
+pub struct BundleExecutor {
+    provider: Arc<dyn Provider>,
+    relay: Arc<Relay>,
+    builders: Arc<Vec<String>>,
+    simulation_endpoint: Option<String>,
+    retarget_blocks: u64,
+    dry_run: Option<Arc<DryRun>>,
+}
+
+impl BundleExecutor {
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        reputation_key: PrivateKeySigner,
+        builders: Vec<String>,
+    ) -> Self {
+        Self {
+            provider,
+            relay: Arc::new(Relay {
+                client: reqwest::Client::new(),
+                reputation_key,
+            }),
+            builders: Arc::new(builders),
+            simulation_endpoint: None,
+            retarget_blocks: 0,
+            dry_run: None,
+        }
+    }
+
+    pub fn new_with_flashbots(
+        provider: Arc<dyn Provider>,
+        reputation_key: PrivateKeySigner,
+    ) -> Self {
+        Self::new(
+            provider,
+            reputation_key,
+            vec!["https://relay.flashbots.net".to_string()],
+        )
+    }
+
+    /// Simulate every bundle with `eth_callBundle` on `endpoint` first, and drop it if any transaction that isn't
+    /// allowed to revert fails
+    pub fn with_call_bundle<T: Into<String>>(mut self, endpoint: T) -> Self {
+        self.simulation_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Send the bundle again for the block after the head, up to `max_blocks` times, while it's not included and its
+    /// `max_timestamp` hasn't passed
+    pub fn with_retarget(mut self, max_blocks: u64) -> Self {
+        self.retarget_blocks = max_blocks;
+        self
+    }
+
+    /// Simulate the transactions of bundles and record them in `dry_run` instead of sending them. Each transaction is
+    /// simulated on its own with `eth_call`, not on top of the previous ones; use `with_call_bundle` for that.
+    pub fn with_dry_run(mut self, dry_run: Arc<DryRun>) -> Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    async fn call_bundle(&self, endpoint: &str, bundle: &Bundle) -> eyre::Result<()> {
+        let params = json!({
+            "txs": bundle.txs,
+            "blockNumber": format!("{:#x}", bundle.target_block),
+            "stateBlockNumber": "latest",
+        });
+
+        let result = self
+            .relay
+            .request(endpoint, "eth_callBundle", params)
+            .await
+            .wrap_err("fail to simulate bundle")?;
+
+        let results = result
+            .get("results")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        for tx in results {
+            let failure = tx.get("error").or_else(|| tx.get("revert"));
+            let hash = tx.get("txHash").and_then(Value::as_str).unwrap_or_default();
+
+            if let Some(failure) = failure {
+                let allowed = bundle
+                    .reverting_hashes
+                    .iter()
+                    .any(|h| h.to_string().eq_ignore_ascii_case(hash));
+
+                eyre::ensure!(allowed, "tx {hash} reverted: {failure}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Executor<Bundle> for BundleExecutor {
+    fn name(&self) -> &str {
+        "BundleExecutor"
+    }
+
+    async fn execute(&self, action: Bundle) -> eyre::Result<()> {
+        if action.txs.is_empty() {
+            tracing::error!("empty bundle");
+            return Ok(());
+        }
+
+        if let Some(endpoint) = &self.simulation_endpoint {
+            if let Err(err) = self.call_bundle(endpoint, &action).await {
+                tracing::error!(block = action.target_block, "dropping bundle: {err:#}");
+                return Ok(());
+            }
+        }
+
+        if let Some(dry_run) = &self.dry_run {
+            for tx in action.txs {
+                dry_run.record_raw(self.provider.as_ref(), tx).await;
+            }
+            return Ok(());
+        }
+
+        self.relay.send_bundle(&self.builders, &action).await;
+
+        if self.retarget_blocks == 0 {
+            return Ok(());
+        }
+
+        let provider = self.provider.clone();
+        let relay = self.relay.clone();
+        let builders = self.builders.clone();
+        let retarget_blocks = self.retarget_blocks;
+
+        tokio::spawn(async move {
+            let mut bundle = action;
+            let first_tx = keccak256(&bundle.txs[0]);
+
+            for _ in 0..retarget_blocks {
+                // wait for the target block to be mined
+                let head = loop {
+                    match provider.get_block_number().await {
+                        Ok(head) if head >= bundle.target_block => break head,
+                        Ok(_) => {}
+                        Err(err) => tracing::warn!("failed to get block number: {err:#}"),
+                    }
+
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                };
+
+                match provider.get_transaction_receipt(first_tx).await {
+                    Ok(Some(receipt)) => {
+                        tracing::info!(block = ?receipt.block_number, tx = ?first_tx, "bundle included");
+                        return;
+                    }
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!(tx = ?first_tx, "failed to get receipt: {err:#}"),
+                }
+
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                if bundle.max_timestamp.is_some_and(|max| now >= max) {
+                    tracing::info!(tx = ?first_tx, "bundle expired, stop retargeting");
+                    return;
+                }
+
+                // the head may have moved past the target already
+                bundle.target_block = head + 1;
+                relay.send_bundle(&builders, &bundle).await;
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::{
+    consensus::{transaction::SignerRecoverable, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    primitives::{keccak256, Address, Bytes, TxHash, TxKind, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+};
+use eyre::WrapErr;
+
+use crate::executor::simulation::{SimulationMethod, SimulationOutcome};
+
+/// A transaction that would have been sent, with its simulated outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunRecord {
+    /// Unix timestamp of the action
+    pub at: u64,
+    pub from: Option<Address>,
+    /// `None` for contract creations
+    pub to: Option<Address>,
+    pub value: U256,
+    pub nonce: Option<u64>,
+    pub gas_limit: Option<u64>,
+    /// Only known if the transaction was signed
+    pub hash: Option<TxHash>,
+    pub raw: Option<Bytes>,
+    pub outcome: SimulationOutcome,
+}
+
+impl DryRunRecord {
+    /// Gas used in the simulation if known, or the gas limit of the transaction
+    pub fn gas(&self) -> Option<u64> {
+        match &self.outcome {
+            SimulationOutcome::Success {
+                gas_used: Some(gas),
+                ..
+            } => Some(*gas),
+            _ => self.gas_limit,
+        }
+    }
+}
+
+/// Summary of a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DryRunReport {
+    /// Every transaction of the run, including those whose record was discarded
+    pub total: usize,
+    pub succeeded: usize,
+    pub reverted: usize,
+    /// Transactions whose simulation itself failed
+    pub failed: usize,
+    /// Sum of [`DryRunRecord::gas`] over the transactions that would succeed
+    pub gas: u64,
+    /// Sum of the value of the transactions that would succeed
+    pub value: U256,
+    /// The latest records, see [`DryRun::with_max_records`]
+    pub records: Vec<DryRunRecord>,
+}
+
+impl DryRunReport {
+    fn add(&mut self, record: &DryRunRecord) {
+        self.total += 1;
+
+        match &record.outcome {
+            SimulationOutcome::Success { .. } => {
+                self.succeeded += 1;
+                self.gas += record.gas().unwrap_or_default();
+                self.value += record.value;
+            }
+            SimulationOutcome::Reverted { .. } => self.reverted += 1,
+            SimulationOutcome::Failed(_) => self.failed += 1,
+        }
+    }
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# {} txs: {} succeeded, {} reverted, {} failed to simulate, gas {}, value {}",
+            self.total, self.succeeded, self.reverted, self.failed, self.gas, self.value
+        )?;
+        writeln!(f, "at\tfrom\tto\tvalue\tnonce\tgas\thash\toutcome")?;
+
+        for r in &self.records {
+            let outcome = match &r.outcome {
+                SimulationOutcome::Success { .. } => "success".to_string(),
+                SimulationOutcome::Reverted { reason, .. } => {
+                    format!("reverted: {}", reason.as_deref().unwrap_or("unknown"))
+                }
+                SimulationOutcome::Failed(err) => format!("failed: {err}"),
+            };
+
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                r.at,
+                display(r.from),
+                display(r.to),
+                r.value,
+                display(r.nonce),
+                display(r.gas()),
+                display(r.hash),
+                outcome.replace(['\t', '\n'], " ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn display<T: fmt::Display>(v: Option<T>) -> String {
+    v.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+/// Paper trading mode of `TransactionSender`, `RawTransactionSender`, `BroadcastSender` and `BundleExecutor`: actions
+/// are simulated and recorded instead of being sent. Share one `DryRun` between executors to get a single report of
+/// the run. Other executors, e.g. `TelegramMessageDispatcher`, have no dry-run mode.
+#[derive(Debug)]
+pub struct DryRun {
+    sign: bool,
+    method: SimulationMethod,
+    max_records: usize,
+    state: Mutex<DryRunState>,
+}
+
+#[derive(Debug, Default)]
+struct DryRunState {
+    records: VecDeque<DryRunRecord>,
+    /// Totals over every record, including discarded ones
+    summary: DryRunReport,
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DryRun {
+    /// Create a new `DryRun` that signs transactions and simulates them with `eth_call`
+    pub fn new() -> Self {
+        Self {
+            sign: true,
+            method: SimulationMethod::EthCall,
+            max_records: 10_000,
+            state: Default::default(),
+        }
+    }
+
+    /// Keep only the latest `max_records` records, 10,000 by default, so a long run doesn't grow without bound. The
+    /// totals of the report still cover the whole run.
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    /// Don't sign transaction requests, so neither nonces nor signers are touched
+    pub fn without_signing(mut self) -> Self {
+        self.sign = false;
+        self
+    }
+
+    pub fn with_simulation_method(mut self, method: SimulationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn signs(&self) -> bool {
+        self.sign
+    }
+
+    /// Simulate `tx` and record it, along with its signed form if any
+    pub async fn record(
+        &self,
+        provider: &dyn Provider,
+        tx: &TransactionRequest,
+        raw: Option<Bytes>,
+    ) {
+        let outcome = self.method.simulate(provider, tx).await;
+
+        let record = DryRunRecord {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            from: tx.from,
+            to: match tx.to {
+                Some(TxKind::Call(to)) => Some(to),
+                _ => None,
+            },
+            value: tx.value.unwrap_or_default(),
+            nonce: tx.nonce,
+            gas_limit: tx.gas,
+            hash: raw.as_ref().map(keccak256),
+            raw,
+            outcome,
+        };
+
+        tracing::info!(
+            account = ?record.from,
+            tx = ?record.hash,
+            gas = ?record.gas(),
+            outcome = ?record.outcome,
+            "dry run, not sending tx"
+        );
+
+        let mut state = self.state.lock().unwrap();
+        state.summary.add(&record);
+
+        if state.records.len() >= self.max_records {
+            state.records.pop_front();
+        }
+        if self.max_records > 0 {
+            state.records.push_back(record);
+        }
+    }
+
+    /// Decode a signed transaction, then simulate and record it
+    pub async fn record_raw(&self, provider: &dyn Provider, raw: Bytes) {
+        let tx = match TxEnvelope::decode_2718(&mut raw.as_ref()) {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::error!(tx = ?keccak256(&raw), "failed to decode tx: {err:#}");
+                return;
+            }
+        };
+
+        let from = match tx.recover_signer() {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::error!(tx = ?tx.tx_hash(), "failed to recover signer: {err:#}");
+                return;
+            }
+        };
+
+        let request = TransactionRequest::from_transaction_with_sender(tx, from);
+        self.record(provider, &request, Some(raw)).await;
+    }
+
+    /// The latest records, see [`Self::with_max_records`]
+    pub fn records(&self) -> Vec<DryRunRecord> {
+        self.state.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    pub fn report(&self) -> DryRunReport {
+        let state = self.state.lock().unwrap();
+
+        DryRunReport {
+            records: state.records.iter().cloned().collect(),
+            ..state.summary.clone()
+        }
+    }
+
+    /// Write the report of the run to `path`, as a summary line followed by one tab separated line per kept record
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> eyre::Result<()> {
+        let path = path.as_ref();
+
+        std::fs::write(path, self.report().to_string())
+            .wrap_err_with(|| format!("fail to write dry run report {path:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::bytes,
+        providers::{mock::Asserter, ProviderBuilder},
+    };
+
+    use super::*;
+
+    const FROM: Address = Address::repeat_byte(1);
+    const TO: Address = Address::repeat_byte(2);
+
+    fn record(value: u64, outcome: SimulationOutcome) -> DryRunRecord {
+        DryRunRecord {
+            at: 100,
+            from: Some(FROM),
+            to: Some(TO),
+            value: U256::from(value),
+            nonce: Some(7),
+            gas_limit: Some(50_000),
+            hash: None,
+            raw: None,
+            outcome,
+        }
+    }
+
+    fn success(gas_used: Option<u64>) -> SimulationOutcome {
+        SimulationOutcome::Success {
+            output: Bytes::new(),
+            gas_used,
+        }
+    }
+
+    fn reverted() -> SimulationOutcome {
+        SimulationOutcome::Reverted {
+            reason: Some("too\tlate".to_string()),
+            output: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn sums_up_records() {
+        let mut report = DryRunReport::default();
+
+        report.add(&record(10, success(Some(21_000))));
+        report.add(&record(5, success(None)));
+        report.add(&record(1_000, reverted()));
+        report.add(&record(
+            1_000,
+            SimulationOutcome::Failed("timeout".to_string()),
+        ));
+
+        assert_eq!(
+            (
+                report.total,
+                report.succeeded,
+                report.reverted,
+                report.failed
+            ),
+            (4, 2, 1, 1)
+        );
+        // the gas limit stands in for the gas used when unknown
+        assert_eq!(report.gas, 21_000 + 50_000);
+        assert_eq!(report.value, U256::from(15));
+    }
+
+    #[test]
+    fn formats_the_report() {
+        let mut report = DryRunReport::default();
+        let records = [record(10, success(Some(21_000))), record(0, reverted())];
+
+        for record in &records {
+            report.add(record);
+        }
+        report.records = records.to_vec();
+
+        let text = report.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "# 2 txs: 1 succeeded, 1 reverted, 0 failed to simulate, gas 21000, value 10"
+        );
+        assert_eq!(lines[1], "at\tfrom\tto\tvalue\tnonce\tgas\thash\toutcome");
+        assert_eq!(
+            lines[2],
+            format!("100\t{FROM}\t{TO}\t10\t7\t21000\t-\tsuccess")
+        );
+        // tabs in the outcome don't break the columns
+        assert_eq!(
+            lines[3],
+            format!("100\t{FROM}\t{TO}\t0\t7\t50000\t-\treverted: too late")
+        );
+        assert_eq!(lines.len(), 4);
+    }
+
+    async fn record_calls(dry_run: &DryRun, values: &[u64]) {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+
+        for value in values {
+            asserter.push_success(&bytes!("01"));
+
+            let tx = TransactionRequest::default()
+                .from(FROM)
+                .to(TO)
+                .value(U256::from(*value));
+            dry_run.record(&provider, &tx, None).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_latest_records() {
+        let dry_run = DryRun::new().with_max_records(2);
+        record_calls(&dry_run, &[1, 2, 3]).await;
+
+        let report = dry_run.report();
+        let values: Vec<U256> = report.records.iter().map(|r| r.value).collect();
+
+        assert_eq!(values, vec![U256::from(2), U256::from(3)]);
+        assert_eq!(dry_run.records(), report.records);
+        // the totals still cover the whole run
+        assert_eq!((report.total, report.succeeded), (3, 3));
+        assert_eq!(report.value, U256::from(6));
+    }
+
+    #[tokio::test]
+    async fn keeps_only_totals_without_records() {
+        let dry_run = DryRun::new().with_max_records(0);
+        record_calls(&dry_run, &[1, 2]).await;
+
+        let report = dry_run.report();
+
+        assert!(report.records.is_empty());
+        assert_eq!((report.total, report.succeeded), (2, 2));
+        assert_eq!(report.value, U256::from(3));
+    }
+}
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::{
+    network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder},
+    primitives::{Bytes, TxHash, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+};
+
+use crate::executor::broadcast::BroadcastSender;
+
+/// Nodes only accept a replacement at the same nonce if it pays at least this much more
+const MIN_BUMP_PERCENT: u64 = 10;
+
+/// How [`TransactionSender`](crate::executor::transaction::TransactionSender) deals with a transaction that is not
+/// included in time.
+#[derive(Debug, Clone)]
+pub struct EscalationPolicy {
+    after_blocks: u64,
+    bump_percent: u64,
+    max_fee_per_gas: u128,
+    cancel: bool,
+    poll_interval: Duration,
+}
+
+impl EscalationPolicy {
+    /// Re-sign a transaction at the same nonce with its fees bumped by `bump_percent` (at least 10%) whenever it's
+    /// not included within `after_blocks` blocks, as long as the fee stays within `max_fee_per_gas`.
+    pub fn new(after_blocks: u64, bump_percent: u64, max_fee_per_gas: u128) -> Self {
+        Self {
+            after_blocks: after_blocks.max(1),
+            bump_percent: bump_percent.max(MIN_BUMP_PERCENT),
+            max_fee_per_gas,
+            cancel: false,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Instead of re-sending the transaction, replace it with an empty transfer to the sender itself
+    pub fn cancel_to_self(mut self) -> Self {
+        self.cancel = true;
+        self
+    }
+
+    /// How often to check for new blocks
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    fn bump(&self, fee: u128) -> u128 {
+        fee + (fee * self.bump_percent as u128).div_ceil(100)
+    }
+
+    /// The replacement of `tx` with bumped fees, or `None` if it would exceed the fee cap
+    fn replacement(&self, tx: &TransactionRequest) -> Option<TransactionRequest> {
+        let mut replacement = match self.cancel {
+            true => TransactionRequest {
+                from: tx.from,
+                to: tx.from.map(Into::into),
+                value: Some(U256::ZERO),
+                input: Default::default(),
+                gas: Some(21_000),
+                nonce: tx.nonce,
+                chain_id: tx.chain_id,
+                gas_price: tx.gas_price,
+                max_fee_per_gas: tx.max_fee_per_gas,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+                ..Default::default()
+            },
+            false => tx.clone(),
+        };
+
+        if let Some(gas_price) = tx.gas_price {
+            let bumped = self.bump(gas_price).min(self.max_fee_per_gas);
+            if bumped < self.min_replacement(gas_price) {
+                return None;
+            }
+            replacement.gas_price = Some(bumped);
+        }
+
+        if let Some(max_fee) = tx.max_fee_per_gas {
+            let bumped = self.bump(max_fee).min(self.max_fee_per_gas);
+            if bumped < self.min_replacement(max_fee) {
+                return None;
+            }
+            replacement.max_fee_per_gas = Some(bumped);
+
+            let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+            replacement.max_priority_fee_per_gas = Some(self.bump(priority_fee).min(bumped));
+        }
+
+        Some(replacement)
+    }
+
+    fn min_replacement(&self, fee: u128) -> u128 {
+        fee + (fee * MIN_BUMP_PERCENT as u128).div_ceil(100)
+    }
+}
+
+/// Watch a submitted transaction in the background and replace it according to `policy` until one of its versions
+/// is included, or the fee cap is reached. Replacements go through `broadcast` if set, like the original.
+pub(crate) fn spawn_escalation(
+    policy: EscalationPolicy,
+    provider: Arc<dyn Provider>,
+    submission_provider: Arc<dyn Provider>,
+    broadcast: Option<Arc<BroadcastSender>>,
+    signer: EthereumWallet,
+    tx: TransactionRequest,
+    tx_hash: TxHash,
+) {
+    tokio::spawn(async move {
+        let (account, nonce) = match (tx.from, tx.nonce) {
+            (Some(account), Some(nonce)) => (account, nonce),
+            _ => return,
+        };
+
+        let mut tx = tx;
+        let mut tx_hash = tx_hash;
+        let mut last_block = 0;
+        let mut sent_at = None;
+
+        loop {
+            tokio::time::sleep(policy.poll_interval).await;
+
+            let block = match provider.get_block_number().await {
+                Ok(v) if v > last_block => v,
+                Ok(_) => continue,
+                Err(err) => {
+                    tracing::warn!(?account, nonce, "failed to get block number: {err:#}");
+                    continue;
+                }
+            };
+
+            last_block = block;
+            let since = *sent_at.get_or_insert(block);
+
+            match provider.get_transaction_count(account).latest().await {
+                Ok(count) if count > nonce => {
+                    tracing::debug!(?account, nonce, "tx nonce used, stop escalating");
+                    return;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::warn!(?account, nonce, "failed to get nonce: {err:#}");
+                    continue;
+                }
+            }
+
+            if block < since + policy.after_blocks {
+                continue;
+            }
+
+            let replacement = match policy.replacement(&tx) {
+                Some(v) => v,
+                None => {
+                    tracing::warn!(?account, nonce, tx = ?tx_hash, "fee cap reached, stop escalating");
+                    return;
+                }
+            };
+
+            let raw_tx: Bytes = match replacement.clone().build(&signer).await {
+                Ok(v) => v.encoded_2718().into(),
+                Err(err) => {
+                    tracing::error!(?account, nonce, "failed to build replacement tx: {err:#}");
+                    return;
+                }
+            };
+
+            let send_result: eyre::Result<TxHash> = match &broadcast {
+                Some(broadcast) => broadcast.broadcast(&raw_tx).await,
+                None => submission_provider
+                    .send_raw_transaction(&raw_tx)
+                    .await
+                    .map(|v| *v.tx_hash())
+                    .map_err(Into::into),
+            };
+
+            match send_result {
+                Ok(replacement_hash) => {
+                    tracing::info!(
+                        ?account,
+                        nonce,
+                        replaced = ?tx_hash,
+                        cancel = policy.cancel,
+                        "sent replacement tx: {:#x}",
+                        replacement_hash
+                    );
+
+                    tx = replacement;
+                    tx_hash = replacement_hash;
+                    sent_at = Some(block);
+                }
+                Err(err) => {
+                    // the nonce check on the next block tells whether it failed because a version got included
+                    tracing::warn!(?account, nonce, "failed to send replacement tx: {err:#}");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder,
+        primitives::{Address, TxKind},
+    };
+
+    use super::*;
+
+    const FROM: Address = Address::repeat_byte(1);
+
+    fn eip1559_tx(max_fee: u128, priority_fee: u128) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_from(FROM)
+            .with_to(Address::repeat_byte(2))
+            .with_value(U256::from(7))
+            .with_input(Bytes::from_static(&[1, 2, 3, 4]))
+            .with_nonce(3)
+            .with_gas_limit(100_000)
+            .with_max_fee_per_gas(max_fee)
+            .with_max_priority_fee_per_gas(priority_fee)
+    }
+
+    #[test]
+    fn bumps_eip1559_fees() {
+        let policy = EscalationPolicy::new(1, 20, 1_000);
+        let replacement = policy.replacement(&eip1559_tx(100, 10)).unwrap();
+
+        assert_eq!(replacement.max_fee_per_gas, Some(120));
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(12));
+        assert_eq!(replacement, eip1559_tx(120, 12));
+    }
+
+    #[test]
+    fn bumps_legacy_gas_price() {
+        let policy = EscalationPolicy::new(1, 20, 1_000);
+        let tx = TransactionRequest::default()
+            .with_from(FROM)
+            .with_nonce(3)
+            .with_gas_price(50);
+
+        let replacement = policy.replacement(&tx).unwrap();
+
+        assert_eq!(replacement.gas_price, Some(60));
+        assert_eq!(replacement.max_fee_per_gas, None);
+    }
+
+    #[test]
+    fn bumps_at_least_ten_percent() {
+        let policy = EscalationPolicy::new(1, 5, 1_000);
+        let replacement = policy.replacement(&eip1559_tx(100, 10)).unwrap();
+
+        assert_eq!(replacement.max_fee_per_gas, Some(110));
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(11));
+    }
+
+    #[test]
+    fn rounds_bumps_up() {
+        let policy = EscalationPolicy::new(1, 10, 1_000);
+        let replacement = policy.replacement(&eip1559_tx(15, 1)).unwrap();
+
+        // 10% of 15 is 1.5, a bump of 1 would be rejected by nodes
+        assert_eq!(replacement.max_fee_per_gas, Some(17));
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(2));
+    }
+
+    #[test]
+    fn caps_fees_as_long_as_the_bump_is_enough() {
+        let policy = EscalationPolicy::new(1, 50, 115);
+        let replacement = policy.replacement(&eip1559_tx(100, 100)).unwrap();
+
+        assert_eq!(replacement.max_fee_per_gas, Some(115));
+        // the priority fee never exceeds the max fee
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(115));
+    }
+
+    #[test]
+    fn stops_once_the_cap_prevents_a_valid_replacement() {
+        let policy = EscalationPolicy::new(1, 50, 105);
+
+        assert_eq!(policy.replacement(&eip1559_tx(100, 10)), None);
+
+        let legacy = TransactionRequest::default().with_gas_price(100);
+        assert_eq!(policy.replacement(&legacy), None);
+    }
+
+    #[test]
+    fn cancels_with_an_empty_transfer_to_self() {
+        let policy = EscalationPolicy::new(1, 20, 1_000).cancel_to_self();
+        let replacement = policy.replacement(&eip1559_tx(100, 10)).unwrap();
+
+        assert_eq!(replacement.from, Some(FROM));
+        assert_eq!(replacement.to, Some(TxKind::Call(FROM)));
+        assert_eq!(replacement.value, Some(U256::ZERO));
+        assert_eq!(replacement.input.input(), None);
+        assert_eq!(replacement.gas, Some(21_000));
+        assert_eq!(replacement.nonce, Some(3));
+        assert_eq!(replacement.max_fee_per_gas, Some(120));
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(12));
+    }
+
+    #[test]
+    fn waits_at_least_one_block() {
+        assert_eq!(EscalationPolicy::new(0, 20, 1_000).after_blocks, 1);
+    }
+}
@@ -0,0 +1,184 @@
+use alloy::{eips::BlockNumberOrTag, providers::Provider};
+
+/// How [`TransactionSender`](crate::executor::transaction::TransactionSender) fills the EIP-1559 fees of
+/// transactions that don't set them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeStrategy {
+    /// `max_fee_per_gas` is the latest base fee times `multiplier`, plus `priority_fee`
+    BaseFeeMultiplier { multiplier: f64, priority_fee: u128 },
+    /// The priority fee is the `percentile` of the priority fees paid over the last `blocks` blocks, averaged, and
+    /// `max_fee_per_gas` is the next block's base fee times `base_fee_multiplier` plus that priority fee
+    FeeHistory {
+        blocks: u64,
+        percentile: f64,
+        base_fee_multiplier: f64,
+    },
+    /// Always use these fees
+    Fixed {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+}
+
+impl FeeStrategy {
+    /// Returns `(max_fee_per_gas, max_priority_fee_per_gas)`
+    pub async fn fees(&self, provider: &dyn Provider) -> eyre::Result<(u128, u128)> {
+        match *self {
+            Self::BaseFeeMultiplier {
+                multiplier,
+                priority_fee,
+            } => {
+                let block = provider
+                    .get_block_by_number(BlockNumberOrTag::Latest)
+                    .await?
+                    .ok_or_else(|| eyre::eyre!("latest block not found"))?;
+
+                let base_fee = block
+                    .header
+                    .base_fee_per_gas
+                    .ok_or_else(|| eyre::eyre!("latest block has no base fee"))?;
+
+                Ok((
+                    scale(base_fee as u128, multiplier) + priority_fee,
+                    priority_fee,
+                ))
+            }
+            Self::FeeHistory {
+                blocks,
+                percentile,
+                base_fee_multiplier,
+            } => {
+                let history = provider
+                    .get_fee_history(blocks, BlockNumberOrTag::Latest, &[percentile])
+                    .await?;
+
+                let base_fee = history
+                    .next_block_base_fee()
+                    .ok_or_else(|| eyre::eyre!("fee history has no base fee"))?;
+
+                let rewards: Vec<u128> = history
+                    .reward
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|block| block.first().copied())
+                    .collect();
+
+                let priority_fee = match rewards.len() {
+                    0 => 0,
+                    n => rewards.iter().sum::<u128>() / n as u128,
+                };
+
+                Ok((
+                    scale(base_fee, base_fee_multiplier) + priority_fee,
+                    priority_fee,
+                ))
+            }
+            Self::Fixed {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Ok((max_fee_per_gas, max_priority_fee_per_gas)),
+        }
+    }
+}
+
+fn scale(value: u128, multiplier: f64) -> u128 {
+    (value as f64 * multiplier).ceil() as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus,
+        providers::{mock::Asserter, ProviderBuilder},
+        rpc::types::{Block, FeeHistory, Header},
+    };
+
+    use super::*;
+
+    fn provider() -> (impl Provider, Asserter) {
+        let asserter = Asserter::new();
+        (
+            ProviderBuilder::new().connect_mocked_client(asserter.clone()),
+            asserter,
+        )
+    }
+
+    fn block(base_fee: Option<u64>) -> Block {
+        Block::empty(Header::new(consensus::Header {
+            base_fee_per_gas: base_fee,
+            ..Default::default()
+        }))
+    }
+
+    fn history(base_fees: Vec<u128>, reward: Option<Vec<Vec<u128>>>) -> FeeHistory {
+        FeeHistory {
+            gas_used_ratio: vec![0.5; base_fees.len().saturating_sub(1)],
+            base_fee_per_gas: base_fees,
+            reward,
+            oldest_block: 100,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn multiplies_the_latest_base_fee() {
+        let (provider, asserter) = provider();
+        let strategy = FeeStrategy::BaseFeeMultiplier {
+            multiplier: 1.5,
+            priority_fee: 2,
+        };
+
+        asserter.push_success(&block(Some(101)));
+        assert_eq!(strategy.fees(&provider).await.unwrap(), (152 + 2, 2));
+
+        asserter.push_success(&block(None));
+        assert!(strategy.fees(&provider).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn averages_fee_history_rewards() {
+        let (provider, asserter) = provider();
+        let strategy = FeeStrategy::FeeHistory {
+            blocks: 3,
+            percentile: 50.0,
+            base_fee_multiplier: 2.0,
+        };
+
+        // the last base fee is the one of the next block
+        asserter.push_success(&history(
+            vec![90, 95, 98, 100],
+            Some(vec![vec![1], vec![2], vec![6]]),
+        ));
+        assert_eq!(strategy.fees(&provider).await.unwrap(), (200 + 3, 3));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_no_priority_fee_without_rewards() {
+        let (provider, asserter) = provider();
+        let strategy = FeeStrategy::FeeHistory {
+            blocks: 3,
+            percentile: 50.0,
+            base_fee_multiplier: 1.0,
+        };
+
+        asserter.push_success(&history(vec![90, 100], Some(vec![])));
+        assert_eq!(strategy.fees(&provider).await.unwrap(), (100, 0));
+
+        asserter.push_success(&history(vec![90, 100], None));
+        assert_eq!(strategy.fees(&provider).await.unwrap(), (100, 0));
+
+        asserter.push_success(&history(vec![], None));
+        assert!(strategy.fees(&provider).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn uses_fixed_fees_as_is() {
+        let (provider, _) = provider();
+        let strategy = FeeStrategy::Fixed {
+            max_fee_per_gas: 30,
+            max_priority_fee_per_gas: 1,
+        };
+
+        assert_eq!(strategy.fees(&provider).await.unwrap(), (30, 1));
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+use alloy::signers::local::PrivateKeySigner;
+use eyre::WrapErr;
+
+/// Where the password of an encrypted JSON keystore comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystorePassword {
+    /// The value of an environment variable
+    Env(String),
+    /// The content of a file, without the trailing newline
+    File(PathBuf),
+}
+
+impl KeystorePassword {
+    fn read(&self) -> eyre::Result<String> {
+        match self {
+            Self::Env(name) => std::env::var(name)
+                .wrap_err_with(|| format!("fail to read keystore password from ${name}")),
+            Self::File(path) => {
+                let password = std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("fail to read keystore password from {path:?}"))?;
+
+                Ok(password.trim_end_matches(['\r', '\n']).to_string())
+            }
+        }
+    }
+}
+
+/// Decrypt the JSON keystore at `path`, e.g. one created by `cast wallet import`
+pub fn load_keystore<P: AsRef<Path>>(
+    path: P,
+    password: &KeystorePassword,
+) -> eyre::Result<PrivateKeySigner> {
+    let path = path.as_ref();
+    let password = password.read()?;
+
+    let signer = PrivateKeySigner::decrypt_keystore(path, password)
+        .wrap_err_with(|| format!("fail to decrypt keystore {path:?}"))?;
+
+    tracing::info!("loaded signer {:#x} from keystore", signer.address());
+
+    Ok(signer)
+}
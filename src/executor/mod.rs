@@ -1,7 +1,31 @@
+#[cfg(feature = "flashbots")]
+pub mod bundle;
 pub mod dummy;
 
+#[cfg(feature = "ethereum")]
+pub mod broadcast;
+#[cfg(feature = "ethereum")]
+pub mod dry_run;
+#[cfg(feature = "ethereum")]
+pub mod escalation;
+#[cfg(feature = "ethereum")]
+pub mod fee;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(feature = "ethereum")]
+pub mod nonce_manager;
 #[cfg(feature = "ethereum")]
 pub mod raw_transaction;
+#[cfg(feature = "ethereum")]
+pub mod raw_transaction_log;
+#[cfg(feature = "ethereum")]
+pub mod remote_signer;
+#[cfg(feature = "ethereum")]
+pub mod risk_guard;
+#[cfg(feature = "ethereum")]
+pub mod signing;
+#[cfg(feature = "ethereum")]
+pub mod simulation;
 
 #[cfg(feature = "telegram")]
 pub mod telegram_message;
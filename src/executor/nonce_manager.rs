@@ -0,0 +1,221 @@
+use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use alloy::{primitives::Address, providers::Provider};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct SignerNonces {
+    /// The nonce after the highest one handed out
+    next: u64,
+    /// Nonces below `next` that were handed back and can be reused
+    released: BTreeSet<u64>,
+}
+
+/// Hands out nonces per signer from a local counter, so back-to-back transactions of the same signer don't race for
+/// the same nonce. The counter starts from the signer's pending transaction count, and is synced again whenever the
+/// node reports a nonce conflict.
+pub struct NonceManager {
+    provider: Arc<dyn Provider>,
+    nonces: Mutex<HashMap<Address, SignerNonces>>,
+}
+
+impl NonceManager {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider,
+            nonces: Default::default(),
+        }
+    }
+
+    /// Reserve the next nonce of `account`. It must be handed back with [`NonceManager::release`] if it ends up not
+    /// being used, otherwise later transactions will be stuck behind the gap.
+    pub async fn reserve(&self, account: Address) -> eyre::Result<u64> {
+        let mut nonces = self.nonces.lock().await;
+
+        let signer = match nonces.entry(account) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(SignerNonces {
+                next: self.fetch(account).await?,
+                ..Default::default()
+            }),
+        };
+
+        if let Some(nonce) = signer.released.pop_first() {
+            return Ok(nonce);
+        }
+
+        signer.next += 1;
+        Ok(signer.next - 1)
+    }
+
+    /// Hand back a reserved nonce that was not used, so it's reserved again next
+    pub async fn release(&self, account: Address, nonce: u64) {
+        let mut nonces = self.nonces.lock().await;
+
+        let signer = match nonces.get_mut(&account) {
+            Some(v) if nonce < v.next => v,
+            _ => return,
+        };
+
+        signer.released.insert(nonce);
+
+        // shrink the counter instead of leaving released nonces at its top
+        while signer.next > 0 && signer.released.remove(&(signer.next - 1)) {
+            signer.next -= 1;
+        }
+    }
+
+    /// Note a nonce that was used without being reserved, e.g. set explicitly on a transaction
+    pub async fn observe(&self, account: Address, nonce: u64) {
+        let mut nonces = self.nonces.lock().await;
+
+        if let Some(signer) = nonces.get_mut(&account) {
+            signer.released.remove(&nonce);
+            signer.next = signer.next.max(nonce + 1);
+        }
+    }
+
+    /// Sync the counter of `account` with its pending transaction count again, and forget released nonces
+    pub async fn resync(&self, account: Address) -> eyre::Result<u64> {
+        let mut nonces = self.nonces.lock().await;
+
+        let next = self.fetch(account).await?;
+        nonces.insert(
+            account,
+            SignerNonces {
+                next,
+                ..Default::default()
+            },
+        );
+
+        tracing::info!(?account, nonce = next, "nonce resynced");
+
+        Ok(next)
+    }
+
+    async fn fetch(&self, account: Address) -> eyre::Result<u64> {
+        Ok(self
+            .provider
+            .get_transaction_count(account)
+            .pending()
+            .await?)
+    }
+}
+
+/// Whether a submission error means the nonce was already used, and the local counter is out of sync
+pub(crate) fn is_nonce_conflict(err: &impl std::fmt::Display) -> bool {
+    let err = err.to_string().to_lowercase();
+    err.contains("nonce too low") || err.contains("already known")
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::providers::{mock::Asserter, ProviderBuilder};
+
+    use super::*;
+
+    const ACCOUNT: Address = Address::repeat_byte(1);
+
+    /// A nonce manager whose first fetch of the pending transaction count returns `count`
+    fn nonce_manager(count: u64) -> (NonceManager, Asserter) {
+        let asserter = Asserter::new();
+        asserter.push_success(&format!("{count:#x}"));
+
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        (NonceManager::new(Arc::new(provider)), asserter)
+    }
+
+    #[tokio::test]
+    async fn reserves_consecutive_nonces_from_pending_count() {
+        let (nonce_manager, _) = nonce_manager(5);
+
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 5);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 6);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn reuses_released_nonces_lowest_first() {
+        let (nonce_manager, _) = nonce_manager(0);
+
+        for _ in 0..4 {
+            nonce_manager.reserve(ACCOUNT).await.unwrap();
+        }
+
+        nonce_manager.release(ACCOUNT, 2).await;
+        nonce_manager.release(ACCOUNT, 1).await;
+
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 1);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 2);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn releasing_the_top_nonces_shrinks_the_counter() {
+        let (nonce_manager, _) = nonce_manager(0);
+
+        for _ in 0..3 {
+            nonce_manager.reserve(ACCOUNT).await.unwrap();
+        }
+
+        // out of order, so 1 is only given back to the counter once 2 is
+        nonce_manager.release(ACCOUNT, 1).await;
+        nonce_manager.release(ACCOUNT, 2).await;
+
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 1);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 2);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn ignores_releases_of_nonces_never_handed_out() {
+        let (nonce_manager, _) = nonce_manager(0);
+
+        nonce_manager.reserve(ACCOUNT).await.unwrap();
+        nonce_manager.release(ACCOUNT, 5).await;
+        nonce_manager.release(Address::ZERO, 0).await;
+
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn observed_nonces_are_not_handed_out() {
+        let (nonce_manager, _) = nonce_manager(0);
+
+        for _ in 0..3 {
+            nonce_manager.reserve(ACCOUNT).await.unwrap();
+        }
+
+        nonce_manager.release(ACCOUNT, 0).await;
+        nonce_manager.observe(ACCOUNT, 0).await;
+        nonce_manager.observe(ACCOUNT, 9).await;
+
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 10);
+    }
+
+    #[tokio::test]
+    async fn resync_forgets_released_nonces() {
+        let (nonce_manager, asserter) = nonce_manager(0);
+
+        for _ in 0..3 {
+            nonce_manager.reserve(ACCOUNT).await.unwrap();
+        }
+        nonce_manager.release(ACCOUNT, 0).await;
+
+        asserter.push_success(&"0x8");
+        assert_eq!(nonce_manager.resync(ACCOUNT).await.unwrap(), 8);
+        assert_eq!(nonce_manager.reserve(ACCOUNT).await.unwrap(), 8);
+    }
+
+    #[test]
+    fn detects_nonce_conflicts() {
+        assert!(is_nonce_conflict(
+            &"server returned an error response: nonce too low"
+        ));
+        assert!(is_nonce_conflict(&"Already known"));
+        assert!(!is_nonce_conflict(
+            &"insufficient funds for gas * price + value"
+        ));
+    }
+}
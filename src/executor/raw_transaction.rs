@@ -7,15 +7,29 @@ use async_trait::async_trait;
 use eyre::Result;
 use std::sync::Arc;
 
+use crate::executor::dry_run::DryRun;
 use crate::types::Executor;
 
+/// Sends raw transactions through `provider`. A failed send returns an error, see [`TransactionSigner::chain`].
+///
+/// [`TransactionSigner::chain`]: crate::executor::signing::TransactionSigner::chain
 pub struct RawTransactionSender {
     provider: Arc<dyn Provider>,
+    dry_run: Option<Arc<DryRun>>,
 }
 
 impl RawTransactionSender {
     pub fn new(provider: Arc<dyn Provider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            dry_run: None,
+        }
+    }
+
+    /// Simulate and record transactions in `dry_run` instead of sending them
+    pub fn with_dry_run(mut self, dry_run: Arc<DryRun>) -> Self {
+        self.dry_run = Some(dry_run);
+        self
     }
 }
 
@@ -23,7 +37,7 @@ impl RawTransactionSender {
     pub fn new_http(url: &str) -> Self {
         let provider = ProviderBuilder::default().connect_http(url.parse().unwrap());
         let provider = Arc::new(provider);
-        Self { provider }
+        Self::new(provider)
     }
 
     pub fn new_with_flashbots() -> Self {
@@ -54,18 +68,22 @@ impl Executor<Bytes> for RawTransactionSender {
     }
 
     async fn execute(&self, action: Bytes) -> Result<()> {
+        if let Some(dry_run) = &self.dry_run {
+            dry_run.record_raw(self.provider.as_ref(), action).await;
+            return Ok(());
+        }
+
         let send_result = self.provider.send_raw_transaction(&action).await;
 
         match send_result {
             Ok(tx) => {
                 tracing::info!(tx = ?tx.tx_hash(), "sent tx");
+                Ok(())
             }
             Err(err) => {
                 let tx_hash = keccak256(&action);
-                tracing::error!(tx = ?tx_hash, "failed to send tx: {:#}", err);
+                Err(eyre::eyre!("failed to send tx {tx_hash:?}: {err:#}"))
             }
         }
-
-        Ok(())
     }
 }
@@ -0,0 +1,57 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use alloy::primitives::{keccak256, Bytes};
+use async_trait::async_trait;
+use eyre::WrapErr;
+
+use crate::types::Executor;
+
+/// Appends raw transactions to a file for audit, one `<unix timestamp> <tx hash> <raw tx>` line each. Nothing is sent.
+pub struct RawTransactionLog {
+    file: Arc<Mutex<File>>,
+}
+
+impl RawTransactionLog {
+    /// Open `path` for appending, creating it if it doesn't exist
+    pub fn new<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .wrap_err_with(|| format!("fail to open raw transaction log {path:?}"))?;
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+}
+
+#[async_trait]
+impl Executor<Bytes> for RawTransactionLog {
+    fn name(&self) -> &str {
+        "RawTransactionLog"
+    }
+
+    async fn execute(&self, action: Bytes) -> eyre::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let line = format!("{now} {} {action}\n", keccak256(&action));
+
+        let file = self.file.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut file = file.lock().unwrap();
+            file.write_all(line.as_bytes())?;
+            file.flush()
+        })
+        .await?
+        .wrap_err("fail to write raw transaction log")
+    }
+}
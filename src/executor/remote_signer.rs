@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::{SignableTransaction, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    network::TxSigner,
+    primitives::{Address, Bytes},
+    providers::{Provider, RootProvider},
+    rpc::types::eth::TransactionRequest,
+    signers::{self, Signature},
+};
+use async_trait::async_trait;
+
+/// Signs transactions with `eth_signTransaction` on a remote signer, e.g. Web3Signer, so the key never has to be in
+/// the bot's memory. It can be used anywhere a `TxSigner` is expected, e.g. `TransactionSender::new_with_signers`.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    provider: Arc<dyn Provider>,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Create a new `RemoteSigner` signing for `address` through `provider`
+    pub fn new(provider: Arc<dyn Provider>, address: Address) -> Self {
+        Self { provider, address }
+    }
+
+    pub fn new_http(url: &str, address: Address) -> Self {
+        let provider = Arc::new(RootProvider::<_>::new_http(url.parse().unwrap()));
+        Self::new(provider, address)
+    }
+
+    /// A `RemoteSigner` for every account returned by `eth_accounts` on `provider`
+    pub async fn accounts(provider: Arc<dyn Provider>) -> eyre::Result<Vec<Self>> {
+        let accounts = provider.get_accounts().await?;
+
+        Ok(accounts
+            .into_iter()
+            .map(|address| Self::new(provider.clone(), address))
+            .collect())
+    }
+
+    fn request(
+        &self,
+        tx: &dyn SignableTransaction<Signature>,
+    ) -> signers::Result<TransactionRequest> {
+        if tx.is_eip4844() || tx.is_eip7702() {
+            return Err(signers::Error::other(
+                "blob and set code transactions are not supported by the remote signer",
+            ));
+        }
+
+        let mut request = TransactionRequest {
+            from: Some(self.address),
+            to: Some(tx.kind()),
+            value: Some(tx.value()),
+            input: tx.input().clone().into(),
+            nonce: Some(tx.nonce()),
+            chain_id: tx.chain_id(),
+            gas: Some(tx.gas_limit()),
+            transaction_type: Some(tx.ty()),
+            access_list: tx.access_list().cloned(),
+            ..Default::default()
+        };
+
+        match tx.is_dynamic_fee() {
+            true => {
+                request.max_fee_per_gas = Some(tx.max_fee_per_gas());
+                request.max_priority_fee_per_gas = tx.max_priority_fee_per_gas();
+            }
+            false => request.gas_price = tx.gas_price(),
+        }
+
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> signers::Result<Signature> {
+        let request = self.request(tx)?;
+
+        let raw: Bytes = self
+            .provider
+            .root()
+            .raw_request("eth_signTransaction".into(), (request,))
+            .await
+            .map_err(signers::Error::other)?;
+
+        let signed = TxEnvelope::decode_2718(&mut raw.as_ref())
+            .map_err(|err| signers::Error::other(format!("invalid signed transaction: {err}")))?;
+        let signature = *signed.signature();
+
+        // make sure the remote signer signed exactly this transaction, with the expected key
+        let signer = signature.recover_address_from_prehash(&tx.signature_hash())?;
+        if signer != self.address {
+            return Err(signers::Error::other(format!(
+                "remote signer signed a different transaction, or with {signer:#x} instead of {:#x}",
+                self.address
+            )));
+        }
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder,
+        primitives::U256,
+        providers::{mock::Asserter, ProviderBuilder},
+        signers::local::PrivateKeySigner,
+    };
+
+    use super::*;
+    use crate::executor::signing::TransactionSigner;
+
+    fn tx(from: Address) -> TransactionRequest {
+        TransactionRequest::default()
+            .with_from(from)
+            .with_to(Address::repeat_byte(2))
+            .with_value(U256::from(1))
+            .with_nonce(7)
+            .with_chain_id(1)
+            .with_gas_limit(21_000)
+            .with_max_fee_per_gas(100)
+            .with_max_priority_fee_per_gas(1)
+    }
+
+    fn remote_signer(asserter: &Asserter, address: Address) -> TransactionSigner {
+        let provider = Arc::new(ProviderBuilder::new().connect_mocked_client(asserter.clone()));
+        TransactionSigner::new_with_signers(vec![RemoteSigner::new(provider, address)])
+    }
+
+    #[tokio::test]
+    async fn signs_with_eth_sign_transaction() {
+        let key = PrivateKeySigner::random();
+        let expected = TransactionSigner::new(vec![key.clone()])
+            .sign(tx(key.address()))
+            .await
+            .unwrap();
+
+        let asserter = Asserter::new();
+        asserter.push_success(&expected.raw);
+
+        let signed = remote_signer(&asserter, key.address())
+            .sign(tx(key.address()))
+            .await
+            .unwrap();
+
+        assert_eq!(signed, expected);
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_of_another_key() {
+        let key = PrivateKeySigner::random();
+        let other = PrivateKeySigner::random();
+
+        let mut forged = tx(key.address());
+        forged.from = Some(other.address());
+        let forged = TransactionSigner::new(vec![other])
+            .sign(forged)
+            .await
+            .unwrap();
+
+        let asserter = Asserter::new();
+        asserter.push_success(&forged.raw);
+
+        let result = remote_signer(&asserter, key.address())
+            .sign(tx(key.address()))
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_signature_of_another_transaction() {
+        let key = PrivateKeySigner::random();
+
+        let mut other_tx = tx(key.address());
+        other_tx.value = Some(U256::from(1_000_000));
+        let other_tx = TransactionSigner::new(vec![key.clone()])
+            .sign(other_tx)
+            .await
+            .unwrap();
+
+        let asserter = Asserter::new();
+        asserter.push_success(&other_tx.raw);
+
+        let result = remote_signer(&asserter, key.address())
+            .sign(tx(key.address()))
+            .await;
+
+        assert!(result.is_err());
+    }
+}
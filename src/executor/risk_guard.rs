@@ -0,0 +1,730 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::{
+    consensus::{transaction::SignerRecoverable, Transaction as _, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    primitives::{Address, Bytes, FixedBytes, U256},
+    providers::Provider,
+    rpc::types::eth::TransactionRequest,
+};
+use async_trait::async_trait;
+use eyre::WrapErr;
+use tokio::sync::Mutex;
+
+use crate::executor::fee::FeeStrategy;
+use crate::types::{ActionSubmitter, Executor};
+
+/// Limits enforced per signer by [`RiskGuard`]. `None` disables a rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    pub max_value_per_tx: Option<U256>,
+    /// Max value sent over the rolling window
+    pub max_value_per_window: Option<U256>,
+    /// Max gas spend over the rolling window, as gas limit times max fee per gas. Requests that don't set them are
+    /// estimated, see [`RiskGuard::with_fee_strategy`].
+    pub max_gas_spend_per_window: Option<U256>,
+    pub max_txs_per_block: Option<u64>,
+    pub allowed_destinations: Option<HashSet<Address>>,
+    pub allowed_selectors: Option<HashSet<FixedBytes<4>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RiskViolation {
+    #[error("value {value} is above the per tx limit {max}")]
+    ValueTooHigh { value: U256, max: U256 },
+    #[error("value spent in window would be {spent}, above the limit {max}")]
+    WindowValueExceeded { spent: U256, max: U256 },
+    #[error("gas spent in window would be {spent}, above the limit {max}")]
+    WindowGasExceeded { spent: U256, max: U256 },
+    #[error("fail to estimate gas spend: {0}")]
+    GasSpendEstimation(String),
+    #[error("fail to get block number: {0}")]
+    BlockNumberUnavailable(String),
+    #[error("already sent {count} txs in block {block}")]
+    TooManyTxsInBlock { block: u64, count: u64 },
+    #[error("destination {0:?} is not allowed")]
+    DestinationNotAllowed(Option<Address>),
+    #[error("selector {0:?} is not allowed")]
+    SelectorNotAllowed(Option<FixedBytes<4>>),
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(String),
+}
+
+/// Emitted when [`RiskGuard`] rejects an action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskAlert {
+    pub signer: Option<Address>,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub violation: RiskViolation,
+}
+
+/// What the rules look at in a transaction
+struct TxSummary {
+    from: Address,
+    to: Option<Address>,
+    value: U256,
+    gas_spend: Option<U256>,
+    selector: Option<FixedBytes<4>>,
+}
+
+impl TxSummary {
+    fn from_request(tx: &TransactionRequest) -> Result<Self, RiskViolation> {
+        let from = tx
+            .from
+            .ok_or_else(|| RiskViolation::InvalidTransaction("missing sender address".into()))?;
+
+        let max_fee = tx.max_fee_per_gas.or(tx.gas_price);
+
+        Ok(Self {
+            from,
+            to: tx.to.and_then(|to| to.to().copied()),
+            value: tx.value.unwrap_or_default(),
+            gas_spend: tx
+                .gas
+                .zip(max_fee)
+                .map(|(gas, fee)| U256::from(gas) * U256::from(fee)),
+            selector: tx
+                .input
+                .input()
+                .and_then(|input| input.get(..4))
+                .map(FixedBytes::from_slice),
+        })
+    }
+
+    fn from_raw(raw: &Bytes) -> Result<Self, RiskViolation> {
+        let tx = TxEnvelope::decode_2718(&mut raw.as_ref())
+            .map_err(|err| RiskViolation::InvalidTransaction(err.to_string()))?;
+
+        let from = tx
+            .recover_signer()
+            .map_err(|err| RiskViolation::InvalidTransaction(err.to_string()))?;
+
+        Ok(Self {
+            from,
+            to: tx.to(),
+            value: tx.value(),
+            gas_spend: Some(U256::from(tx.gas_limit()) * U256::from(tx.max_fee_per_gas())),
+            selector: tx.input().get(..4).map(FixedBytes::from_slice),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct SignerState {
+    /// `(unix timestamp, value, gas spend)` of the txs sent in the window
+    sent: VecDeque<(u64, U256, U256)>,
+    /// `(block, txs sent)` of the latest block a tx was sent in
+    block: (u64, u64),
+}
+
+/// Enforces [`RiskLimits`] in front of `TransactionSender`, `RawTransactionSender`, or any executor of transaction
+/// requests or raw transactions. Rejected actions are dropped and reported to the alert submitter. A rejected raw
+/// transaction also fails with an error, so that a `SigningExecutor` in front gives back the nonce it reserved.
+pub struct RiskGuard<E> {
+    inner: E,
+    provider: Arc<dyn Provider>,
+    limits: RiskLimits,
+    signer_limits: HashMap<Address, RiskLimits>,
+    window: Duration,
+    alert: Option<Arc<dyn ActionSubmitter<RiskAlert>>>,
+    state_file: Option<PathBuf>,
+    state: Mutex<HashMap<Address, SignerState>>,
+    fee_strategy: Option<FeeStrategy>,
+    gas_margin_percent: u64,
+}
+
+impl<E> RiskGuard<E> {
+    /// Create a new `RiskGuard` applying `limits` to every signer, with a rolling window of one hour
+    pub fn new(inner: E, provider: Arc<dyn Provider>, limits: RiskLimits) -> Self {
+        Self {
+            inner,
+            provider,
+            limits,
+            signer_limits: HashMap::new(),
+            window: Duration::from_secs(3600),
+            alert: None,
+            state_file: None,
+            state: Default::default(),
+            fee_strategy: None,
+            gas_margin_percent: 0,
+        }
+    }
+
+    /// Apply `limits` to `signer` instead of the default ones
+    pub fn with_signer_limits(mut self, signer: Address, limits: RiskLimits) -> Self {
+        self.signer_limits.insert(signer, limits);
+        self
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Submit a [`RiskAlert`] whenever an action is rejected
+    pub fn with_alert_submitter(mut self, alert: Arc<dyn ActionSubmitter<RiskAlert>>) -> Self {
+        self.alert = Some(alert);
+        self
+    }
+
+    /// Estimate the fees of transaction requests that don't set them with `fee_strategy`, instead of the provider's
+    /// EIP-1559 estimate. Use the strategy of the `TransactionSender` behind the guard, so the gas spend rule sees
+    /// the fees that will be paid.
+    pub fn with_fee_strategy(mut self, fee_strategy: FeeStrategy) -> Self {
+        self.fee_strategy = Some(fee_strategy);
+        self
+    }
+
+    /// Add `margin_percent` to the gas estimate of transaction requests that don't set `gas`, like
+    /// `TransactionSender::with_gas_estimation`
+    pub fn with_gas_estimation(mut self, margin_percent: u64) -> Self {
+        self.gas_margin_percent = margin_percent;
+        self
+    }
+
+    /// Gas limit times max fee per gas of `tx`, estimating the ones it doesn't set the way `TransactionSender` fills
+    /// them
+    async fn estimate_gas_spend(&self, tx: &TransactionRequest) -> eyre::Result<U256> {
+        let max_fee = match (tx.max_fee_per_gas.or(tx.gas_price), &self.fee_strategy) {
+            (Some(v), _) => v,
+            (None, Some(fee_strategy)) => fee_strategy.fees(self.provider.as_ref()).await?.0,
+            (None, None) => self.provider.estimate_eip1559_fees().await?.max_fee_per_gas,
+        };
+
+        let gas = match tx.gas {
+            Some(v) => v,
+            None => {
+                let gas = self.provider.estimate_gas(tx.clone()).await?;
+                gas + gas * self.gas_margin_percent / 100
+            }
+        };
+
+        Ok(U256::from(gas) * U256::from(max_fee))
+    }
+
+    /// Keep the window and block counters in `path`, and load them from it if it exists
+    pub fn with_state_file<P: Into<PathBuf>>(mut self, path: P) -> eyre::Result<Self> {
+        let path = path.into();
+
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("fail to read risk state {path:?}"))?;
+            *self.state.get_mut() = parse_state(&content)
+                .wrap_err_with(|| format!("fail to parse risk state {path:?}"))?;
+        }
+
+        self.state_file = Some(path);
+        Ok(self)
+    }
+
+    /// Check `tx` against the limits of its signer, and count it if it's allowed
+    async fn admit(&self, tx: &TxSummary) -> Result<(), RiskViolation> {
+        let limits = self.limits(&tx.from);
+
+        if let Some(max) = limits.max_value_per_tx {
+            if tx.value > max {
+                return Err(RiskViolation::ValueTooHigh {
+                    value: tx.value,
+                    max,
+                });
+            }
+        }
+
+        if let Some(allowed) = &limits.allowed_destinations {
+            if !tx.to.is_some_and(|to| allowed.contains(&to)) {
+                return Err(RiskViolation::DestinationNotAllowed(tx.to));
+            }
+        }
+
+        if let Some(allowed) = &limits.allowed_selectors {
+            if !tx
+                .selector
+                .is_some_and(|selector| allowed.contains(&selector))
+            {
+                return Err(RiskViolation::SelectorNotAllowed(tx.selector));
+            }
+        }
+
+        let gas_spend = tx.gas_spend.unwrap_or_default();
+
+        let block = match limits.max_txs_per_block {
+            Some(_) => Some(
+                self.provider
+                    .get_block_number()
+                    .await
+                    .map_err(|err| RiskViolation::BlockNumberUnavailable(format!("{err:#}")))?,
+            ),
+            None => None,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut state = self.state.lock().await;
+        let signer = state.entry(tx.from).or_default();
+
+        while let Some((sent_at, _, _)) = signer.sent.front() {
+            if sent_at + self.window.as_secs() > now {
+                break;
+            }
+            signer.sent.pop_front();
+        }
+
+        if let Some(max) = limits.max_value_per_window {
+            let spent = signer.sent.iter().map(|(_, value, _)| *value).sum::<U256>() + tx.value;
+            if spent > max {
+                return Err(RiskViolation::WindowValueExceeded { spent, max });
+            }
+        }
+
+        if let Some(max) = limits.max_gas_spend_per_window {
+            let spent = signer.sent.iter().map(|(_, _, gas)| *gas).sum::<U256>() + gas_spend;
+            if spent > max {
+                return Err(RiskViolation::WindowGasExceeded { spent, max });
+            }
+        }
+
+        if let (Some(max), Some(block)) = (limits.max_txs_per_block, block) {
+            if signer.block.0 != block {
+                signer.block = (block, 0);
+            }
+
+            if signer.block.1 >= max {
+                return Err(RiskViolation::TooManyTxsInBlock {
+                    block,
+                    count: signer.block.1,
+                });
+            }
+
+            signer.block.1 += 1;
+        }
+
+        signer.sent.push_back((now, tx.value, gas_spend));
+
+        // still under the lock, so that the file always ends up with the latest state
+        if let Some(path) = &self.state_file {
+            if let Err(err) = persist_state(path.clone(), format_state(&state)).await {
+                tracing::error!(?path, "failed to persist risk state: {err:#}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn limits(&self, signer: &Address) -> &RiskLimits {
+        self.signer_limits.get(signer).unwrap_or(&self.limits)
+    }
+
+    fn reject(
+        &self,
+        signer: Option<Address>,
+        to: Option<Address>,
+        value: U256,
+        violation: RiskViolation,
+    ) {
+        tracing::error!(?signer, ?to, %value, "rejected tx: {violation}");
+
+        if let Some(alert) = &self.alert {
+            alert.submit(RiskAlert {
+                signer,
+                to,
+                value,
+                violation,
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl<E: Executor<TransactionRequest>> Executor<TransactionRequest> for RiskGuard<E> {
+    fn name(&self) -> &str {
+        "RiskGuard"
+    }
+
+    async fn execute(&self, action: TransactionRequest) -> eyre::Result<()> {
+        let to = action.to.and_then(|to| to.to().copied());
+        let value = action.value.unwrap_or_default();
+
+        let admitted = match TxSummary::from_request(&action) {
+            Ok(mut tx) => {
+                // fees and gas are usually only filled by the `TransactionSender` behind the guard
+                if tx.gas_spend.is_none()
+                    && self.limits(&tx.from).max_gas_spend_per_window.is_some()
+                {
+                    match self.estimate_gas_spend(&action).await {
+                        Ok(v) => tx.gas_spend = Some(v),
+                        Err(err) => {
+                            let violation = RiskViolation::GasSpendEstimation(format!("{err:#}"));
+                            self.reject(action.from, to, value, violation);
+                            return Ok(());
+                        }
+                    }
+                }
+
+                self.admit(&tx).await
+            }
+            Err(violation) => Err(violation),
+        };
+
+        match admitted {
+            Ok(()) => self.inner.execute(action).await,
+            Err(violation) => {
+                self.reject(action.from, to, value, violation);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<E: Executor<Bytes>> Executor<Bytes> for RiskGuard<E> {
+    fn name(&self) -> &str {
+        "RiskGuard"
+    }
+
+    async fn execute(&self, action: Bytes) -> eyre::Result<()> {
+        let tx = match TxSummary::from_raw(&action) {
+            Ok(v) => v,
+            Err(violation) => {
+                let err = eyre::eyre!("rejected tx: {violation}");
+                self.reject(None, None, U256::ZERO, violation);
+                return Err(err);
+            }
+        };
+
+        match self.admit(&tx).await {
+            Ok(()) => self.inner.execute(action).await,
+            Err(violation) => {
+                let err = eyre::eyre!("rejected tx: {violation}");
+                self.reject(Some(tx.from), tx.to, tx.value, violation);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Replace `path` with `content` through a temporary file, so a crash never leaves a truncated state behind
+async fn persist_state(path: PathBuf, content: String) -> eyre::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, &path)
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// One line per record: `sent <signer> <timestamp> <value> <gas spend>` or `block <signer> <block> <count>`
+fn format_state(state: &HashMap<Address, SignerState>) -> String {
+    let mut out = String::new();
+
+    for (signer, s) in state {
+        let _ = writeln!(out, "block {signer} {} {}", s.block.0, s.block.1);
+
+        for (sent_at, value, gas) in &s.sent {
+            let _ = writeln!(out, "sent {signer} {sent_at} {value} {gas}");
+        }
+    }
+
+    out
+}
+
+fn parse_state(content: &str) -> eyre::Result<HashMap<Address, SignerState>> {
+    let mut state = HashMap::<Address, SignerState>::new();
+
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        match fields.as_slice() {
+            ["block", signer, block, count] => {
+                state.entry(signer.parse()?).or_default().block = (block.parse()?, count.parse()?);
+            }
+            ["sent", signer, sent_at, value, gas] => {
+                state.entry(signer.parse()?).or_default().sent.push_back((
+                    sent_at.parse()?,
+                    value.parse()?,
+                    gas.parse()?,
+                ));
+            }
+            _ => eyre::bail!("invalid line: {line}"),
+        }
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        network::TransactionBuilder,
+        providers::{mock::Asserter, ProviderBuilder},
+        signers::local::PrivateKeySigner,
+    };
+
+    use super::*;
+    use crate::executor::{dummy::Dummy, nonce_manager::NonceManager, signing::TransactionSigner};
+
+    const SIGNER: Address = Address::repeat_byte(1);
+    const TARGET: Address = Address::repeat_byte(2);
+
+    fn guard(limits: RiskLimits) -> (RiskGuard<Dummy>, Asserter) {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        (RiskGuard::new(Dummy, Arc::new(provider), limits), asserter)
+    }
+
+    fn tx(value: u64, gas_spend: u64) -> TxSummary {
+        TxSummary {
+            from: SIGNER,
+            to: Some(TARGET),
+            value: U256::from(value),
+            gas_spend: Some(U256::from(gas_spend)),
+            selector: Some(FixedBytes::new([1, 2, 3, 4])),
+        }
+    }
+
+    #[test]
+    fn state_survives_a_round_trip() {
+        let mut state = HashMap::new();
+        state.insert(
+            SIGNER,
+            SignerState {
+                sent: VecDeque::from([
+                    (100, U256::from(1), U256::from(21_000)),
+                    (200, U256::MAX, U256::ZERO),
+                ]),
+                block: (12, 3),
+            },
+        );
+        state.insert(TARGET, SignerState::default());
+
+        let parsed = parse_state(&format_state(&state)).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[&SIGNER].sent, state[&SIGNER].sent);
+        assert_eq!(parsed[&SIGNER].block, (12, 3));
+        assert!(parsed[&TARGET].sent.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_state() {
+        assert!(parse_state("").unwrap().is_empty());
+        assert!(parse_state("\n  \n").unwrap().is_empty());
+        assert!(parse_state("sent 0x01 100 1").is_err());
+        assert!(parse_state(&format!("sent {SIGNER} 100 1")).is_err());
+        assert!(parse_state(&format!("block {SIGNER} 12 three")).is_err());
+        assert!(parse_state(&format!("spent {SIGNER} 100 1 1")).is_err());
+    }
+
+    #[tokio::test]
+    async fn enforces_per_tx_value() {
+        let (guard, _) = guard(RiskLimits {
+            max_value_per_tx: Some(U256::from(10)),
+            ..Default::default()
+        });
+
+        assert!(guard.admit(&tx(10, 0)).await.is_ok());
+        assert_eq!(
+            guard.admit(&tx(11, 0)).await,
+            Err(RiskViolation::ValueTooHigh {
+                value: U256::from(11),
+                max: U256::from(10)
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn enforces_window_value_and_gas_spend() {
+        let (guard, _) = guard(RiskLimits {
+            max_value_per_window: Some(U256::from(15)),
+            max_gas_spend_per_window: Some(U256::from(100)),
+            ..Default::default()
+        });
+
+        assert!(guard.admit(&tx(10, 50)).await.is_ok());
+        assert!(matches!(
+            guard.admit(&tx(6, 0)).await,
+            Err(RiskViolation::WindowValueExceeded { .. })
+        ));
+        assert!(matches!(
+            guard.admit(&tx(0, 51)).await,
+            Err(RiskViolation::WindowGasExceeded { .. })
+        ));
+
+        // rejected txs are not counted
+        assert!(guard.admit(&tx(5, 50)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn forgets_txs_out_of_the_window() {
+        let (guard, _) = guard(RiskLimits {
+            max_value_per_window: Some(U256::from(15)),
+            ..Default::default()
+        });
+        let guard = guard.with_window(Duration::ZERO);
+
+        assert!(guard.admit(&tx(10, 0)).await.is_ok());
+        assert!(guard.admit(&tx(10, 0)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn applies_signer_limits_over_default_ones() {
+        let (guard, _) = guard(RiskLimits {
+            max_value_per_tx: Some(U256::from(10)),
+            ..Default::default()
+        });
+        let guard = guard.with_signer_limits(SIGNER, RiskLimits::default());
+
+        assert!(guard.admit(&tx(1_000, 0)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn enforces_destination_and_selector_allow_lists() {
+        let (guard, _) = guard(RiskLimits {
+            allowed_destinations: Some(HashSet::from([TARGET])),
+            allowed_selectors: Some(HashSet::from([FixedBytes::new([1, 2, 3, 4])])),
+            ..Default::default()
+        });
+
+        assert!(guard.admit(&tx(0, 0)).await.is_ok());
+
+        let mut other = tx(0, 0);
+        other.to = None;
+        assert_eq!(
+            guard.admit(&other).await,
+            Err(RiskViolation::DestinationNotAllowed(None))
+        );
+
+        let mut other = tx(0, 0);
+        other.selector = Some(FixedBytes::new([4, 3, 2, 1]));
+        assert!(matches!(
+            guard.admit(&other).await,
+            Err(RiskViolation::SelectorNotAllowed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn enforces_txs_per_block() {
+        let (guard, asserter) = guard(RiskLimits {
+            max_txs_per_block: Some(1),
+            ..Default::default()
+        });
+
+        asserter.push_success(&"0x10");
+        assert!(guard.admit(&tx(0, 0)).await.is_ok());
+
+        asserter.push_success(&"0x10");
+        assert_eq!(
+            guard.admit(&tx(0, 0)).await,
+            Err(RiskViolation::TooManyTxsInBlock {
+                block: 16,
+                count: 1
+            })
+        );
+
+        asserter.push_success(&"0x11");
+        assert!(guard.admit(&tx(0, 0)).await.is_ok());
+
+        asserter.push_failure_msg("node is down");
+        assert!(matches!(
+            guard.admit(&tx(0, 0)).await,
+            Err(RiskViolation::BlockNumberUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn estimates_the_gas_spend_of_requests_without_fees() {
+        let (guard, asserter) = guard(RiskLimits {
+            max_gas_spend_per_window: Some(U256::from(40_000)),
+            ..Default::default()
+        });
+        let guard = guard
+            .with_fee_strategy(FeeStrategy::Fixed {
+                max_fee_per_gas: 1,
+                max_priority_fee_per_gas: 1,
+            })
+            .with_gas_estimation(10);
+
+        let request = TransactionRequest {
+            from: Some(SIGNER),
+            to: Some(TARGET.into()),
+            ..Default::default()
+        };
+
+        // 21,000 gas plus 10% margin at 1 wei
+        asserter.push_success(&"0x5208");
+        guard.execute(request.clone()).await.unwrap();
+        assert_eq!(
+            guard.state.lock().await[&SIGNER].sent[0].2,
+            U256::from(23_100)
+        );
+
+        asserter.push_success(&"0x5208");
+        guard.execute(request).await.unwrap();
+        assert_eq!(guard.state.lock().await[&SIGNER].sent.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn persists_state_to_file() {
+        let path = std::env::temp_dir().join(format!("risk-guard-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let limits = RiskLimits {
+            max_value_per_window: Some(U256::from(15)),
+            ..Default::default()
+        };
+
+        let (first, _) = guard(limits.clone());
+        let first = first.with_state_file(&path).unwrap();
+        assert!(first.admit(&tx(10, 0)).await.is_ok());
+
+        // a restarted guard remembers what was spent
+        let (restarted, _) = guard(limits);
+        let restarted = restarted.with_state_file(&path).unwrap();
+        assert!(restarted.admit(&tx(10, 0)).await.is_err());
+        assert!(restarted.admit(&tx(5, 0)).await.is_ok());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn rejected_raw_transactions_give_back_their_nonce() {
+        let signer = PrivateKeySigner::random();
+        let from = signer.address();
+
+        let asserter = Asserter::new();
+        asserter.push_success(&"0x5");
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter);
+        let nonce_manager = Arc::new(NonceManager::new(Arc::new(provider)));
+
+        let (guard, _) = guard(RiskLimits {
+            max_value_per_tx: Some(U256::from(10)),
+            ..Default::default()
+        });
+        let executor = TransactionSigner::new(vec![signer])
+            .with_nonce_manager(nonce_manager.clone())
+            .chain(guard);
+
+        let request = TransactionRequest::default()
+            .from(from)
+            .to(TARGET)
+            .value(U256::from(11))
+            .gas_limit(21_000)
+            .max_fee_per_gas(2)
+            .max_priority_fee_per_gas(1)
+            .with_chain_id(1);
+
+        assert!(executor.execute(request).await.is_err());
+        assert_eq!(nonce_manager.reserve(from).await.unwrap(), 5);
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use alloy::{
+    consensus::Transaction as _,
+    network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder, TxSigner},
+    primitives::{keccak256, Address, Bytes, TxHash},
+    rpc::types::eth::TransactionRequest,
+    signers::{local::PrivateKeySigner, Signature},
+};
+use async_trait::async_trait;
+
+use crate::executor::nonce_manager::{is_nonce_conflict, NonceManager};
+use crate::types::Executor;
+
+pub(crate) fn wallets<S>(signers: Vec<S>) -> HashMap<Address, EthereumWallet>
+where
+    S: TxSigner<Signature> + Send + Sync + 'static,
+{
+    let signers: HashMap<_, _> = signers
+        .into_iter()
+        .map(|s| (TxSigner::address(&s), EthereumWallet::new(s)))
+        .collect();
+
+    for signer in signers.keys() {
+        tracing::info!("setting up signer {:#x}", signer);
+    }
+
+    signers
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub from: Address,
+    pub nonce: u64,
+    pub hash: TxHash,
+    pub raw: Bytes,
+}
+
+/// Signs `TransactionRequest`s with the signer of their `from` address, without sending them.
+#[derive(Clone)]
+pub struct TransactionSigner {
+    signers: HashMap<Address, EthereumWallet>,
+    nonce_manager: Option<Arc<NonceManager>>,
+}
+
+impl TransactionSigner {
+    pub fn new(signers: Vec<PrivateKeySigner>) -> Self {
+        Self::new_with_signers(signers)
+    }
+
+    /// Create a new `TransactionSigner` with any kind of signer, e.g. a keystore or a remote signer
+    pub fn new_with_signers<S>(signers: Vec<S>) -> Self
+    where
+        S: TxSigner<Signature> + Send + Sync + 'static,
+    {
+        Self::new_with_wallets(wallets(signers))
+    }
+
+    /// Create a new `TransactionSigner` with wallets keyed by the address they sign for. Like `TransactionSender`,
+    /// only `EthereumWallet` is supported, not any `NetworkWallet`.
+    pub fn new_with_wallets(signers: HashMap<Address, EthereumWallet>) -> Self {
+        Self {
+            signers,
+            nonce_manager: None,
+        }
+    }
+
+    /// Fill the nonce of transactions that don't set it from `nonce_manager`
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<NonceManager>) -> Self {
+        self.nonce_manager = Some(nonce_manager);
+        self
+    }
+
+    /// Send the signed transactions to `executor`. `executor` must return an error whenever the transaction was not
+    /// sent, e.g. rejected or failed to broadcast, so the nonce reserved for it is given back. A transaction it drops
+    /// with `Ok` keeps its nonce, leaving a gap that holds back every later transaction of the signer.
+    pub fn chain<E: Executor<Bytes>>(self, executor: E) -> SigningExecutor<E> {
+        SigningExecutor {
+            signer: self,
+            executor,
+        }
+    }
+
+    pub async fn sign(&self, tx: TransactionRequest) -> eyre::Result<SignedTransaction> {
+        self.sign_reserving(tx).await.map(|(signed, _)| signed)
+    }
+
+    /// Sign `tx`, and tell whether its nonce was reserved from the nonce manager
+    async fn sign_reserving(
+        &self,
+        tx: TransactionRequest,
+    ) -> eyre::Result<(SignedTransaction, bool)> {
+        let mut tx = tx;
+
+        let from = tx
+            .from
+            .ok_or_else(|| eyre::eyre!("missing sender address"))?;
+
+        let signer = self
+            .signers
+            .get(&from)
+            .ok_or_else(|| eyre::eyre!("missing signer for {from:#x}"))?;
+
+        let reserved = match (tx.nonce, &self.nonce_manager) {
+            (Some(nonce), Some(nonce_manager)) => {
+                nonce_manager.observe(from, nonce).await;
+                None
+            }
+            (None, Some(nonce_manager)) => {
+                let nonce = nonce_manager.reserve(from).await?;
+                tx.set_nonce(nonce);
+                Some((nonce_manager, nonce))
+            }
+            _ => None,
+        };
+
+        let (nonce, raw): (u64, Bytes) = match tx.build(signer).await {
+            Ok(v) => (v.nonce(), v.encoded_2718().into()),
+            Err(err) => {
+                if let Some((nonce_manager, nonce)) = reserved {
+                    nonce_manager.release(from, nonce).await;
+                }
+                return Err(err.into());
+            }
+        };
+
+        let signed = SignedTransaction {
+            from,
+            nonce,
+            hash: keccak256(&raw),
+            raw,
+        };
+
+        Ok((signed, reserved.is_some()))
+    }
+
+    /// Give back the nonce of `signed` after it failed to be sent, or resync it if it's already used
+    async fn unreserve(&self, signed: &SignedTransaction, err: &eyre::Report) {
+        let nonce_manager = match &self.nonce_manager {
+            Some(v) => v,
+            None => return,
+        };
+
+        if is_nonce_conflict(&format!("{err:#}")) {
+            if let Err(err) = nonce_manager.resync(signed.from).await {
+                tracing::error!(account = ?signed.from, "failed to resync nonce: {err:#}");
+            }
+        } else {
+            nonce_manager.release(signed.from, signed.nonce).await;
+        }
+    }
+}
+
+/// Signs `TransactionRequest` actions with a [`TransactionSigner`] and hands the raw transactions to an
+/// `Executor<Bytes>`, e.g. `RawTransactionSender`, `BroadcastSender`, `RiskGuard` or `RawTransactionLog`. If the
+/// executor returns an error, a nonce reserved for the transaction is given back to the nonce manager, see
+/// [`TransactionSigner::chain`].
+pub struct SigningExecutor<E> {
+    signer: TransactionSigner,
+    executor: E,
+}
+
+#[async_trait]
+impl<E: Executor<Bytes>> Executor<TransactionRequest> for SigningExecutor<E> {
+    fn name(&self) -> &str {
+        "SigningExecutor"
+    }
+
+    async fn execute(&self, action: TransactionRequest) -> eyre::Result<()> {
+        let (signed, reserved) = match self.signer.sign_reserving(action).await {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::error!("failed to sign tx: {err:#}");
+                return Ok(());
+            }
+        };
+
+        tracing::debug!(account = ?signed.from, tx = ?signed.hash, "signed tx");
+
+        let result = self.executor.execute(signed.raw.clone()).await;
+
+        if let (Err(err), true) = (&result, reserved) {
+            self.signer.unreserve(&signed, err).await;
+        }
+
+        result
+    }
+}
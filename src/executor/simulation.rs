@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::Bytes,
+    providers::Provider,
+    rpc::types::{
+        eth::TransactionRequest,
+        simulate::{SimBlock, SimulatePayload},
+    },
+    sol_types::decode_revert_reason,
+    transports::{RpcError, TransportErrorKind},
+};
+
+use crate::METHOD_NOT_FOUND;
+
+/// How a transaction is simulated before it's signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationMethod {
+    /// `eth_call` against the pending block
+    EthCall,
+    /// `eth_simulateV1` against the pending block, falling back to `eth_call` if the node doesn't support it
+    SimulateV1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationOutcome {
+    /// The transaction would succeed. `gas_used` is only known with `eth_simulateV1`.
+    Success {
+        output: Bytes,
+        gas_used: Option<u64>,
+    },
+    /// The transaction would revert, it's not sent
+    Reverted {
+        reason: Option<String>,
+        output: Bytes,
+    },
+    /// The simulation itself failed, the transaction is sent anyway
+    Failed(String),
+}
+
+impl SimulationOutcome {
+    pub fn is_reverted(&self) -> bool {
+        matches!(self, Self::Reverted { .. })
+    }
+}
+
+/// Called with every simulated transaction and its outcome.
+pub type SimulationCallback = Arc<dyn Fn(&TransactionRequest, &SimulationOutcome) + Send + Sync>;
+
+impl SimulationMethod {
+    /// Simulate `tx` against the pending block
+    pub async fn simulate(
+        &self,
+        provider: &dyn Provider,
+        tx: &TransactionRequest,
+    ) -> SimulationOutcome {
+        if *self == Self::SimulateV1 {
+            match simulate_v1(provider, tx).await {
+                Ok(outcome) => return outcome,
+                Err(RpcError::ErrorResp(err)) if err.code == METHOD_NOT_FOUND => {
+                    tracing::debug!("eth_simulateV1 not supported, falling back to eth_call");
+                }
+                Err(err) => return SimulationOutcome::Failed(format!("{err:#}")),
+            }
+        }
+
+        let result = provider
+            .call(tx.clone())
+            .block(BlockNumberOrTag::Pending.into())
+            .await;
+
+        match result {
+            Ok(output) => SimulationOutcome::Success {
+                output,
+                gas_used: None,
+            },
+            Err(err) => {
+                let resp = match err.as_error_resp() {
+                    Some(v) => v,
+                    None => return SimulationOutcome::Failed(format!("{err:#}")),
+                };
+
+                match resp.as_revert_data() {
+                    Some(output) => SimulationOutcome::Reverted {
+                        reason: revert_reason(&output).or_else(|| Some(resp.message.to_string())),
+                        output,
+                    },
+                    None if resp.message.contains("revert") => SimulationOutcome::Reverted {
+                        reason: Some(resp.message.to_string()),
+                        output: Bytes::new(),
+                    },
+                    None => SimulationOutcome::Failed(format!("{err:#}")),
+                }
+            }
+        }
+    }
+}
+
+/// The decoded revert reason, if `output` has one. Empty revert data decodes to an empty reason, so it's skipped.
+fn revert_reason(output: &[u8]) -> Option<String> {
+    decode_revert_reason(output).filter(|reason| !reason.is_empty())
+}
+
+async fn simulate_v1(
+    provider: &dyn Provider,
+    tx: &TransactionRequest,
+) -> Result<SimulationOutcome, RpcError<TransportErrorKind>> {
+    let payload = SimulatePayload::default().extend(SimBlock {
+        calls: vec![tx.clone()],
+        ..Default::default()
+    });
+
+    let blocks = provider.simulate(&payload).pending().await?;
+
+    let call = match blocks
+        .into_iter()
+        .next()
+        .and_then(|block| block.calls.into_iter().next())
+    {
+        Some(v) => v,
+        None => {
+            return Ok(SimulationOutcome::Failed(
+                "eth_simulateV1 returned no call result".to_string(),
+            ))
+        }
+    };
+
+    if call.status {
+        return Ok(SimulationOutcome::Success {
+            output: call.return_data,
+            gas_used: Some(call.gas_used),
+        });
+    }
+
+    let reason = revert_reason(&call.return_data).or(call.error.map(|err| err.message));
+
+    Ok(SimulationOutcome::Reverted {
+        reason,
+        output: call.return_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{bytes, Address},
+        providers::{mock::Asserter, ProviderBuilder},
+        rpc::{
+            json_rpc::ErrorPayload,
+            types::{
+                simulate::{SimCallResult, SimulateError, SimulatedBlock},
+                Block,
+            },
+        },
+        sol_types::{Revert, SolError},
+    };
+
+    use super::*;
+
+    fn provider() -> (impl Provider, Asserter) {
+        let asserter = Asserter::new();
+        (
+            ProviderBuilder::new().connect_mocked_client(asserter.clone()),
+            asserter,
+        )
+    }
+
+    fn tx() -> TransactionRequest {
+        TransactionRequest::default().to(Address::repeat_byte(1))
+    }
+
+    fn error(code: i64, message: &'static str, data: Option<Bytes>) -> ErrorPayload {
+        ErrorPayload {
+            code,
+            message: message.into(),
+            data: data.map(|data| serde_json::value::to_raw_value(&data).unwrap()),
+        }
+    }
+
+    fn revert(reason: &str) -> Bytes {
+        Revert::from(reason).abi_encode().into()
+    }
+
+    #[tokio::test]
+    async fn reports_successful_calls() {
+        let (provider, asserter) = provider();
+
+        asserter.push_success(&bytes!("01"));
+        assert_eq!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Success {
+                output: bytes!("01"),
+                gas_used: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn decodes_revert_data() {
+        let (provider, asserter) = provider();
+
+        asserter.push_failure(error(
+            3,
+            "execution reverted: too late",
+            Some(revert("too late")),
+        ));
+        assert_eq!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Reverted {
+                reason: Some("revert: too late".to_string()),
+                output: revert("too late"),
+            }
+        );
+
+        // undecodable revert data, e.g. a custom error
+        asserter.push_failure(error(3, "execution reverted", Some(bytes!("deadbeef"))));
+        assert_eq!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Reverted {
+                reason: Some("execution reverted".to_string()),
+                output: bytes!("deadbeef"),
+            }
+        );
+
+        asserter.push_failure(error(3, "execution reverted", Some(Bytes::new())));
+        assert_eq!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Reverted {
+                reason: Some("execution reverted".to_string()),
+                output: Bytes::new(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_revert_message() {
+        let (provider, asserter) = provider();
+
+        asserter.push_failure(error(-32000, "execution reverted", None));
+        assert_eq!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Reverted {
+                reason: Some("execution reverted".to_string()),
+                output: Bytes::new(),
+            }
+        );
+
+        asserter.push_failure(error(-32000, "insufficient funds for gas", None));
+        assert!(matches!(
+            SimulationMethod::EthCall.simulate(&provider, &tx()).await,
+            SimulationOutcome::Failed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_simulate_v1_results() {
+        let (provider, asserter) = provider();
+
+        let block = |call: SimCallResult| {
+            vec![SimulatedBlock {
+                inner: Block::<alloy::rpc::types::Transaction>::default(),
+                calls: vec![call],
+            }]
+        };
+
+        asserter.push_success(&block(SimCallResult {
+            return_data: bytes!("01"),
+            gas_used: 21_000,
+            status: true,
+            ..Default::default()
+        }));
+        assert_eq!(
+            SimulationMethod::SimulateV1
+                .simulate(&provider, &tx())
+                .await,
+            SimulationOutcome::Success {
+                output: bytes!("01"),
+                gas_used: Some(21_000)
+            }
+        );
+
+        asserter.push_success(&block(SimCallResult {
+            return_data: Bytes::new(),
+            gas_used: 30_000,
+            status: false,
+            error: Some(SimulateError {
+                code: 3,
+                message: "out of gas".to_string(),
+            }),
+            ..Default::default()
+        }));
+        assert_eq!(
+            SimulationMethod::SimulateV1
+                .simulate(&provider, &tx())
+                .await,
+            SimulationOutcome::Reverted {
+                reason: Some("out of gas".to_string()),
+                output: Bytes::new(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_eth_call_without_simulate_v1() {
+        let (provider, asserter) = provider();
+
+        asserter.push_failure(error(
+            METHOD_NOT_FOUND,
+            "the method eth_simulateV1 does not exist",
+            None,
+        ));
+        asserter.push_success(&bytes!("02"));
+        assert_eq!(
+            SimulationMethod::SimulateV1
+                .simulate(&provider, &tx())
+                .await,
+            SimulationOutcome::Success {
+                output: bytes!("02"),
+                gas_used: None
+            }
+        );
+
+        // other errors are not retried
+        asserter.push_failure(error(-32000, "header not found", None));
+        assert!(matches!(
+            SimulationMethod::SimulateV1
+                .simulate(&provider, &tx())
+                .await,
+            SimulationOutcome::Failed(_)
+        ));
+    }
+}
@@ -1,37 +1,216 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::{local::PrivateKeySigner, Signature};
 use alloy::{
-    network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder},
-    primitives::{keccak256, Address, Bytes},
+    network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder, TxSigner},
+    primitives::{keccak256, Address, Bytes, TxHash},
     providers::{Provider, RootProvider},
     rpc::types::eth::TransactionRequest,
 };
 
+use crate::executor::broadcast::BroadcastSender;
+use crate::executor::dry_run::DryRun;
+use crate::executor::escalation::{spawn_escalation, EscalationPolicy};
+use crate::executor::fee::FeeStrategy;
+use crate::executor::nonce_manager::{is_nonce_conflict, NonceManager};
+use crate::executor::signing::{wallets, TransactionSigner};
+use crate::executor::simulation::{SimulationCallback, SimulationMethod, SimulationOutcome};
 use crate::types::Executor;
 
 pub struct TransactionSender {
     provider: Arc<dyn Provider>,
     signers: HashMap<Address, EthereumWallet>,
     tx_submission_provider: Option<Arc<dyn Provider>>,
+    broadcast: Option<Arc<BroadcastSender>>,
+    nonce_manager: Arc<NonceManager>,
+    escalation: Option<EscalationPolicy>,
+    fee_strategy: Option<FeeStrategy>,
+    gas_margin_percent: Option<u64>,
+    gas_caps: HashMap<Address, u64>,
+    simulation: Option<SimulationMethod>,
+    simulation_callback: Option<SimulationCallback>,
+    dry_run: Option<Arc<DryRun>>,
 }
 
 impl TransactionSender {
     pub fn new(provider: Arc<dyn Provider>, signers: Vec<PrivateKeySigner>) -> Self {
-        let signers: HashMap<_, _> = signers
-            .into_iter()
-            .map(|s| (s.address(), EthereumWallet::new(s)))
-            .collect();
+        Self::new_with_signers(provider, signers)
+    }
 
-        for signer in signers.keys() {
-            tracing::info!("setting up signer {:#x}", signer);
-        }
+    /// Create a new `TransactionSender` with any kind of signer, e.g. a keystore or a remote signer
+    pub fn new_with_signers<S>(provider: Arc<dyn Provider>, signers: Vec<S>) -> Self
+    where
+        S: TxSigner<Signature> + Send + Sync + 'static,
+    {
+        Self::new_with_wallets(provider, wallets(signers))
+    }
 
+    /// Create a new `TransactionSender` with wallets keyed by the address they sign for. Only `EthereumWallet` is
+    /// supported, not any `NetworkWallet`; wrap other signers with `EthereumWallet::new` or use `new_with_signers`.
+    pub fn new_with_wallets(
+        provider: Arc<dyn Provider>,
+        signers: HashMap<Address, EthereumWallet>,
+    ) -> Self {
         Self {
+            nonce_manager: Arc::new(NonceManager::new(provider.clone())),
             provider,
             signers,
             tx_submission_provider: None,
+            broadcast: None,
+            escalation: None,
+            fee_strategy: None,
+            gas_margin_percent: None,
+            gas_caps: HashMap::new(),
+            simulation: None,
+            simulation_callback: None,
+            dry_run: None,
+        }
+    }
+
+    /// Share `nonce_manager` with strategies that reserve nonces before building transactions, or with other senders
+    /// of the same signers
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<NonceManager>) -> Self {
+        self.nonce_manager = nonce_manager;
+        self
+    }
+
+    pub fn nonce_manager(&self) -> Arc<NonceManager> {
+        self.nonce_manager.clone()
+    }
+
+    /// A signer over the same signers and nonce manager, to sign transactions without sending them
+    pub fn transaction_signer(&self) -> TransactionSigner {
+        TransactionSigner::new_with_wallets(self.signers.clone())
+            .with_nonce_manager(self.nonce_manager.clone())
+    }
+
+    /// Send signed transactions through `broadcast` instead of a single endpoint
+    pub fn with_broadcast(mut self, broadcast: Arc<BroadcastSender>) -> Self {
+        self.broadcast = Some(broadcast);
+        self
+    }
+
+    /// Fill the EIP-1559 fees of transactions that set neither `gas_price` nor `max_fee_per_gas`
+    pub fn with_fee_strategy(mut self, fee_strategy: FeeStrategy) -> Self {
+        self.fee_strategy = Some(fee_strategy);
+        self
+    }
+
+    /// Fill `gas` of transactions that don't set it with the estimate plus `margin_percent`
+    pub fn with_gas_estimation(mut self, margin_percent: u64) -> Self {
+        self.gas_margin_percent = Some(margin_percent);
+        self
+    }
+
+    /// Refuse to send transactions of `signer` with a gas limit above `max_gas`
+    pub fn with_gas_cap(mut self, signer: Address, max_gas: u64) -> Self {
+        self.gas_caps.insert(signer, max_gas);
+        self
+    }
+
+    /// Simulate every transaction against the pending block before signing it, and drop it if it would revert
+    pub fn with_simulation(mut self, method: SimulationMethod) -> Self {
+        self.simulation = Some(method);
+        self
+    }
+
+    /// Call `callback` with the outcome of every simulation
+    pub fn with_simulation_callback(
+        mut self,
+        callback: impl Fn(&TransactionRequest, &SimulationOutcome) + Send + Sync + 'static,
+    ) -> Self {
+        self.simulation_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Simulate and record transactions in `dry_run` instead of sending them
+    pub fn with_dry_run(mut self, dry_run: Arc<DryRun>) -> Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    async fn fill_fees_and_gas(
+        &self,
+        account: Address,
+        action: &mut TransactionRequest,
+    ) -> eyre::Result<()> {
+        if let Some(fee_strategy) = &self.fee_strategy {
+            if action.gas_price.is_none() && action.max_fee_per_gas.is_none() {
+                let (max_fee, priority_fee) = fee_strategy.fees(self.provider.as_ref()).await?;
+                action.set_max_fee_per_gas(max_fee);
+                action.set_max_priority_fee_per_gas(priority_fee);
+            }
+        }
+
+        if let Some(margin_percent) = self.gas_margin_percent {
+            if action.gas.is_none() {
+                let gas = self.provider.estimate_gas(action.clone()).await?;
+                action.set_gas_limit(gas + gas * margin_percent / 100);
+            }
+        }
+
+        if let (Some(cap), Some(gas)) = (self.gas_caps.get(&account), action.gas) {
+            eyre::ensure!(gas <= *cap, "gas limit {gas} is above the cap {cap}");
+        }
+
+        Ok(())
+    }
+
+    /// Watch every sent transaction and replace it according to `policy` if it's not included in time
+    pub fn with_escalation(mut self, policy: EscalationPolicy) -> Self {
+        self.escalation = Some(policy);
+        self
+    }
+
+    async fn dry_run(
+        &self,
+        dry_run: &DryRun,
+        account: Address,
+        signer: &EthereumWallet,
+        action: TransactionRequest,
+    ) {
+        let mut action = action;
+
+        if let Err(err) = self.fill_fees_and_gas(account, &mut action).await {
+            tracing::error!(?account, "failed to fill fees and gas: {err:#}");
+            return;
+        }
+
+        if !dry_run.signs() {
+            dry_run.record(self.provider.as_ref(), &action, None).await;
+            return;
+        }
+
+        // the nonce is given back once signed, since nothing is sent
+        let reserved = match action.nonce {
+            Some(_) => None,
+            None => match self.nonce_manager.reserve(account).await {
+                Ok(nonce) => {
+                    action.set_nonce(nonce);
+                    Some(nonce)
+                }
+                Err(err) => {
+                    tracing::error!(?account, "failed to get nonce: {err:#}");
+                    return;
+                }
+            },
+        };
+
+        let built = action.clone().build(signer).await;
+
+        if let Some(nonce) = reserved {
+            self.nonce_manager.release(account, nonce).await;
+        }
+
+        match built {
+            Ok(v) => {
+                let raw_tx: Bytes = v.encoded_2718().into();
+                dry_run
+                    .record(self.provider.as_ref(), &action, Some(raw_tx))
+                    .await;
+            }
+            Err(err) => tracing::error!(?account, "failed to build tx: {err:#}"),
         }
     }
 }
@@ -42,19 +221,9 @@ impl TransactionSender {
         tx_submission_provider: Arc<dyn Provider>,
         signers: Vec<PrivateKeySigner>,
     ) -> Self {
-        let signers: HashMap<_, _> = signers
-            .into_iter()
-            .map(|s| (s.address(), EthereumWallet::new(s)))
-            .collect();
-
-        for signer in signers.keys() {
-            tracing::info!("setting up signer {:#x}", signer);
-        }
-
         Self {
-            provider,
-            signers,
             tx_submission_provider: Some(tx_submission_provider),
+            ..Self::new(provider, signers)
         }
     }
 
@@ -125,51 +294,217 @@ impl Executor<TransactionRequest> for TransactionSender {
             }
         };
 
-        if action.nonce.is_none() {
-            let nonce = match self.provider.get_transaction_count(account).await {
-                Ok(v) => v,
+        if let Some(dry_run) = &self.dry_run {
+            self.dry_run(dry_run, account, signer, action).await;
+            return Ok(());
+        }
+
+        // nonces set by the caller are either reserved from the nonce manager already, or managed by the caller
+        let reserved = match action.nonce {
+            Some(nonce) => {
+                self.nonce_manager.observe(account, nonce).await;
+                None
+            }
+            None => match self.nonce_manager.reserve(account).await {
+                Ok(nonce) => {
+                    action.set_nonce(nonce);
+                    Some(nonce)
+                }
                 Err(err) => {
                     tracing::error!(?account, "failed to get nonce: {err:#}");
                     return Ok(());
                 }
-            };
+            },
+        };
+
+        if let Err(err) = self.fill_fees_and_gas(account, &mut action).await {
+            tracing::error!(?account, "failed to fill fees and gas: {err:#}");
+            if let Some(nonce) = reserved {
+                self.nonce_manager.release(account, nonce).await;
+            }
+            return Ok(());
+        }
 
-            action.set_nonce(nonce);
+        if let Some(method) = &self.simulation {
+            let outcome = method.simulate(self.provider.as_ref(), &action).await;
+
+            if let Some(callback) = &self.simulation_callback {
+                callback(&action, &outcome);
+            }
+
+            match &outcome {
+                SimulationOutcome::Reverted { reason, .. } => {
+                    let reason = reason.as_deref().unwrap_or("unknown");
+                    tracing::error!(?account, reason, "tx would revert, dropping it");
+                    if let Some(nonce) = reserved {
+                        self.nonce_manager.release(account, nonce).await;
+                    }
+                    return Ok(());
+                }
+                SimulationOutcome::Failed(err) => {
+                    tracing::warn!(?account, "failed to simulate tx, sending it anyway: {err}");
+                }
+                SimulationOutcome::Success { .. } => {}
+            }
         }
 
+        let sent = self.escalation.as_ref().map(|_| action.clone());
+
         let raw_tx: Bytes = match action.build(signer).await {
             Ok(v) => v.encoded_2718().into(),
             Err(err) => {
                 tracing::error!(?account, "failed to build tx: {err:#}");
+                if let Some(nonce) = reserved {
+                    self.nonce_manager.release(account, nonce).await;
+                }
                 return Ok(());
             }
         };
 
         tracing::debug!(?account, tx = ?raw_tx, "signed tx");
 
-        let send_result = match &self.tx_submission_provider {
-            Some(dedicated_provider) => dedicated_provider
-                .send_raw_transaction(&raw_tx)
-                .await
-                .map(|v| *v.tx_hash()),
-            None => self
-                .provider
-                .send_raw_transaction(&raw_tx)
-                .await
-                .map(|v| *v.tx_hash()),
-        };
+        let send_result: eyre::Result<TxHash> =
+            match (&self.broadcast, &self.tx_submission_provider) {
+                (Some(broadcast), _) => broadcast.broadcast(&raw_tx).await,
+                (None, Some(dedicated_provider)) => dedicated_provider
+                    .send_raw_transaction(&raw_tx)
+                    .await
+                    .map(|v| *v.tx_hash())
+                    .map_err(Into::into),
+                (None, None) => self
+                    .provider
+                    .send_raw_transaction(&raw_tx)
+                    .await
+                    .map(|v| *v.tx_hash())
+                    .map_err(Into::into),
+            };
 
         let tx_hash = match send_result {
             Ok(v) => v,
             Err(err) => {
                 let hash = keccak256(&raw_tx);
                 tracing::error!(?account, tx = ?hash, "failed to send tx: {err:#}");
+
+                if is_nonce_conflict(&err) {
+                    if let Err(err) = self.nonce_manager.resync(account).await {
+                        tracing::error!(?account, "failed to resync nonce: {err:#}");
+                    }
+                } else if let Some(nonce) = reserved {
+                    self.nonce_manager.release(account, nonce).await;
+                }
+
                 return Ok(());
             }
         };
 
         tracing::info!(?account, "sent tx: {:#x}", tx_hash);
 
+        if let (Some(policy), Some(sent)) = (&self.escalation, sent) {
+            let submission_provider = self
+                .tx_submission_provider
+                .clone()
+                .unwrap_or_else(|| self.provider.clone());
+
+            spawn_escalation(
+                policy.clone(),
+                self.provider.clone(),
+                submission_provider,
+                self.broadcast.clone(),
+                signer.clone(),
+                sent,
+                tx_hash,
+            );
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::providers::{mock::Asserter, ProviderBuilder};
+
+    use super::*;
+
+    const ACCOUNT: Address = Address::repeat_byte(1);
+
+    fn sender() -> (TransactionSender, Asserter) {
+        let asserter = Asserter::new();
+        let provider = ProviderBuilder::new().connect_mocked_client(asserter.clone());
+        (TransactionSender::new(Arc::new(provider), vec![]), asserter)
+    }
+
+    fn request() -> TransactionRequest {
+        TransactionRequest::default()
+            .from(ACCOUNT)
+            .to(Address::repeat_byte(2))
+    }
+
+    #[tokio::test]
+    async fn fills_fees_of_requests_without_them() {
+        let (sender, _) = sender();
+        let sender = sender.with_fee_strategy(FeeStrategy::Fixed {
+            max_fee_per_gas: 30,
+            max_priority_fee_per_gas: 1,
+        });
+
+        let mut tx = request();
+        sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.unwrap();
+        assert_eq!(
+            (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            (Some(30), Some(1))
+        );
+
+        // fees already set on the request are kept
+        let mut tx = request().max_fee_per_gas(50);
+        sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.unwrap();
+        assert_eq!(
+            (tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            (Some(50), None)
+        );
+
+        let mut tx = request().gas_price(40);
+        sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.unwrap();
+        assert_eq!(tx.max_fee_per_gas, None);
+    }
+
+    #[tokio::test]
+    async fn adds_the_margin_to_estimated_gas() {
+        let (sender, asserter) = sender();
+        let sender = sender.with_gas_estimation(20);
+
+        asserter.push_success(&"0xc350");
+        let mut tx = request();
+        sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.unwrap();
+        assert_eq!(tx.gas, Some(60_000));
+
+        // a gas limit already set on the request is kept, without estimating
+        let mut tx = request().gas_limit(21_000);
+        sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.unwrap();
+        assert_eq!(tx.gas, Some(21_000));
+    }
+
+    #[tokio::test]
+    async fn enforces_the_gas_cap_of_the_signer() {
+        let (sender, asserter) = sender();
+        let sender = sender
+            .with_gas_estimation(20)
+            .with_gas_cap(ACCOUNT, 100_000);
+
+        let mut tx = request().gas_limit(100_000);
+        assert!(sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.is_ok());
+
+        let mut tx = request().gas_limit(100_001);
+        assert!(sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.is_err());
+
+        // the cap applies to the gas limit after the margin
+        asserter.push_success(&"0x14c08");
+        let mut tx = request();
+        assert!(sender.fill_fees_and_gas(ACCOUNT, &mut tx).await.is_err());
+
+        // other signers are not capped
+        let other = Address::repeat_byte(3);
+        let mut tx = request().from(other).gas_limit(1_000_000);
+        assert!(sender.fill_fees_and_gas(other, &mut tx).await.is_ok());
+    }
+}
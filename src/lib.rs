@@ -5,6 +5,10 @@ pub mod executor;
 mod macros;
 pub mod types;
 
+/// JSON-RPC error code of a method the node doesn't implement
+#[cfg(feature = "ethereum")]
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+
 pub use async_trait::async_trait;
 pub use engine::Engine;
 pub use types::*;